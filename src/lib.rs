@@ -7,6 +7,28 @@
 //! - Reading binary and ASCII STL files.
 //! - Writing binary and ASCII STL files.
 //! - Generating 3D cube and cone shapes.
+//! - Repairing inconsistent facet orientation, filling holes and removing degenerate facets.
+//! - Checking meshes for watertightness, manifoldness and self-intersections.
+//! - Splitting a mesh into connected components.
+//! - Merging several STL files into one.
+//! - Computing volume, surface area, centroids and inertia.
+//! - Computing axis-aligned and oriented bounding boxes.
+//! - Translating, rotating, scaling and mirroring meshes.
+//! - Detecting and converting length units.
+//! - Simplifying meshes with quadric error decimation.
+//! - Refining meshes with midpoint and Loop subdivision.
+//! - Smoothing noisy meshes with Laplacian and Taubin smoothing.
+//! - Combining closed meshes with union, difference and intersection.
+//! - Computing convex hulls.
+//! - Slicing meshes into closed layer contours.
+//! - Exporting slice contours as SVG and DXF.
+//! - Generating Marlin G-code with perimeters and rectilinear infill.
+//! - Ray casting against meshes through a bounding volume hierarchy.
+//! - Point containment, closest point and signed distance queries.
+//! - Voxelizing meshes into surface or solid grids with STL, raw and MagicaVoxel output.
+//! - Meshing implicit fields and sampled volumes with marching cubes.
+//! - Comparing meshes by Hausdorff distance and RMS deviation.
+//! - Structural diffs of two STL files with JSON output.
 //!
 //! ## Usage
 //!
@@ -17,7 +39,7 @@
 //! #### Parsing an STL File
 //!
 //! ```cmd
-//! stl_parser.exe --output-format <OUTPUT_FORMAT> parse <INPUT>
+//! stl_parser.exe --output-format <OUTPUT_FORMAT> parse [--units <UNIT>] [--to-units <UNIT>] <INPUT>
 //! ```
 //!
 //! #### Generating a Cube
@@ -32,4 +54,100 @@
 //! stl_parser.exe --output-format <OUTPUT_FORMAT> cone <N> <R> <H> <ORIGIN>
 //! ```
 //!
+//! #### Checking a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe check [--self-intersections] <INPUT>
+//! ```
+//!
+//! #### Repairing a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] repair [--orient] [--fill-holes] [--max-hole-size <N>] [--remove-degenerate] <INPUT>
+//! ```
+//!
+//! #### Splitting a Mesh into Parts
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] split [--sort-by <volume|triangles>] <INPUT>
+//! ```
+//!
+//! #### Merging Files
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] merge [--translate <OFFSET>]... [--weld <TOLERANCE>] <INPUTS>...
+//! ```
+//!
+//! #### Printing Mass Properties
+//!
+//! ```cmd
+//! stl_parser.exe info [--density <DENSITY>] <INPUT>
+//! ```
+//!
+//! #### Transforming a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] transform [--translate <OFFSET>] [--rotate <DEGREES>] [--scale <FACTORS>] [--mirror <NORMAL>] [--center] [--fit <SIZE>] [--drop-to-floor] <INPUT>
+//! ```
+//!
+//! #### Decimating a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] decimate [--target <FACETS>] [--ratio <RATIO>] [--max-error <ERROR>] <INPUT>
+//! ```
+//!
+//! #### Subdividing a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] subdivide [--scheme <midpoint|loop>] [--iterations <N>] <INPUT>
+//! ```
+//!
+//! #### Smoothing a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] smooth [--method <laplacian|taubin>] [--weights <uniform|cotangent>] [--iterations <N>] [--lambda <LAMBDA>] [--mu <MU>] [--free-boundary] [--feature-angle <DEGREES>] <INPUT>
+//! ```
+//!
+//! #### Boolean Operations
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] boolean --op <union|difference|intersection> <A> <B>
+//! ```
+//!
+//! #### Convex Hull
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] hull <INPUT>
+//! ```
+//!
+//! #### Slicing a Mesh into Svg Or Dxf Contours
+//!
+//! ```cmd
+//! stl_parser.exe slice [--layer-height <HEIGHT> | --height <HEIGHT>] [--normal <NORMAL>] [--format <svg|dxf>] [--split-layers] <INPUT>
+//! ```
+//!
+//! #### Generating G-Code for an FDM Printer
+//!
+//! ```cmd
+//! stl_parser.exe gcode [--settings <SETTINGS>] <INPUT>
+//! ```
+//!
+//! #### Voxelizing a Mesh
+//!
+//! ```cmd
+//! stl_parser.exe [--output-format <OUTPUT_FORMAT>] voxelize --size <SIZE> [--mode <solid|surface>] [--format <stl|raw|vox>] <INPUT>
+//! ```
+//!
+//! #### Comparing Two Meshes
+//!
+//! ```cmd
+//! stl_parser.exe compare [--samples <SAMPLES>] [--seed <SEED>] [--tolerance <TOLERANCE>] [--per-vertex <CSV>] <A> <B>
+//! ```
+//!
+//! #### Diffing Two STL Files
+//!
+//! ```cmd
+//! stl_parser.exe diff [--tolerance <TOLERANCE>] [--json] <A> <B>
+//! ```
+//!
 pub mod utils;
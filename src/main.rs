@@ -1,8 +1,38 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use anyhow::anyhow;
 use clap::Parser;
 
-use stl_parser::utils::{stl_generator::StlGenerator, stl_parser::StlParser, Command, StlFormat};
+use stl_parser::utils::{
+    bounding_box::{Aabb, Obb},
+    bvh::Bvh,
+    cleanup::{remove_degenerate_facets, CleanupOptions},
+    components::{sort_components, split_components},
+    contour_export::write_contours,
+    convex_hull::convex_hull,
+    csg::boolean,
+    decimate::{decimate, DecimateOptions},
+    diff::StlDiff,
+    gcode::{fits_on_bed, plan_layers, write_gcode_file, PrintSettings},
+    hausdorff::{compare, vertex_deviations, write_deviations},
+    holes::fill_holes,
+    intersection::self_intersections,
+    mass_properties::MassProperties,
+    matrix::Mat4,
+    merge::merge,
+    mesh_report::MeshReport,
+    orientation::orient_facets,
+    slicer::{slice, slice_layers},
+    smoothing::{smooth, SmoothingOptions},
+    stl_generator::StlGenerator,
+    stl_parser::{StlParser, Triangles},
+    subdivision::subdivide,
+    transform::{center_on_origin, drop_to_floor, scale_to_fit, transform},
+    units::{convert_units, guess_unit, Unit},
+    vec3::Vec3,
+    voxel::{voxelize, write_voxels, VoxelFormat},
+    Command, StlFormat, EPS,
+};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -12,7 +42,7 @@ struct Args {
     output: Option<PathBuf>,
 
     /// format of the output file
-    #[arg(long)]
+    #[arg(long, default_value = "binary")]
     #[clap(value_enum)]
     output_format: StlFormat,
 
@@ -21,41 +51,548 @@ struct Args {
     command: Command,
 }
 
+/// `<dir>/<stem><suffix>.stl` next to the input file.
+fn derived_path(input: &Path, suffix: &str) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    input.with_file_name(format!("{}{}.stl", stem, suffix))
+}
+
+/// Unit recorded in the header of the file, if any.
+fn header_unit(input: &Path) -> Option<Unit> {
+    StlParser::read_binary_header(input.to_path_buf())
+        .ok()
+        .and_then(|header| Unit::from_header(&header))
+}
+
+/// Unit recorded by all inputs that record one, `None` if they disagree.
+fn common_unit(inputs: &[PathBuf]) -> Option<Unit> {
+    let mut units = inputs.iter().filter_map(|input| header_unit(input));
+    let first = units.next()?;
+    units.all(|unit| unit == first).then_some(first)
+}
+
+/// Unit recorded in the header of the file, or else guessed from the part size.
+fn input_unit(input: PathBuf, triangles: &Triangles) -> Unit {
+    match header_unit(&input) {
+        Some(unit) => unit,
+        None => {
+            let unit = guess_unit(triangles);
+            eprintln!("no unit in the header, guessed: {}", unit.symbol());
+            unit
+        }
+    }
+}
+
+/// Writes the mesh, recording `unit` in the header.
+fn write_stl(
+    path: PathBuf,
+    triangles: Triangles,
+    format: StlFormat,
+    unit: Option<Unit>,
+) -> anyhow::Result<()> {
+    let header = unit.map(Unit::header).unwrap_or_default();
+    StlParser::write_stl_with_header(path, triangles, format, &header)
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-
-    let output = if let Some(output) = args.output {
-        output
-    } else {
-        match args.command.clone() {
-            Command::Cone { .. } => PathBuf::from("Cone.stl"),
-            Command::Cube { .. } => PathBuf::from("Cube.stl"),
-            Command::Parse { input } => {
-                let mut input = input
-                    .to_string_lossy()
-                    .split_once('.')
-                    .unwrap()
-                    .0
-                    .to_string();
-                input.push_str("_out.stl");
-                PathBuf::from(input)
-            }
-        }
-    };
+    let output = |default: PathBuf| args.output.clone().unwrap_or(default);
 
     match args.command {
-        Command::Cone { n, r, h, origin } => {
-            StlGenerator::generate_cone_stl(n, r, h, origin, output, args.output_format)?
+        Command::Cone { n, r, h, origin } => StlGenerator::generate_cone_stl(
+            n,
+            r,
+            h,
+            origin,
+            output(PathBuf::from("Cone.stl")),
+            args.output_format,
+        )?,
+        Command::Cube { a, origin } => StlGenerator::generate_cube_stl(
+            a,
+            origin,
+            output(PathBuf::from("Cube.stl")),
+            args.output_format,
+        )?,
+        Command::Parse {
+            input,
+            units,
+            to_units,
+        } => {
+            let output = output(derived_path(&input, "_out"));
+            let mut triangles = StlParser::read_stl(input.clone())?;
+
+            let unit = match to_units {
+                Some(to) => {
+                    let from = units.unwrap_or_else(|| input_unit(input, &triangles));
+                    convert_units(&mut triangles, from, to);
+                    Some(to)
+                }
+                None => units.or_else(|| header_unit(&input)),
+            };
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Check {
+            input,
+            self_intersections: check_intersections,
+        } => {
+            let triangles = StlParser::read_stl(input)?;
+            let report = MeshReport::new(&triangles);
+            println!("{}", report);
+
+            let intersections = if check_intersections {
+                let intersections = self_intersections(&triangles, true);
+                println!("self-intersections:    {}", intersections.len());
+                for intersection in &intersections {
+                    let (i, j) = intersection.facets;
+                    match intersection.segment {
+                        Some((a, b)) => println!(
+                            "  facets {} and {}: ({} {} {}) - ({} {} {})",
+                            i, j, a.x, a.y, a.z, b.x, b.y, b.z
+                        ),
+                        None => println!("  facets {} and {}: coplanar overlap", i, j),
+                    }
+                }
+                intersections.len()
+            } else {
+                0
+            };
+
+            if !report.is_watertight() || !report.is_manifold() {
+                return Err(anyhow!("Mesh is not a closed 2-manifold!"));
+            }
+            if intersections > 0 {
+                return Err(anyhow!("Mesh has {} self-intersections!", intersections));
+            }
+        }
+        Command::Repair {
+            input,
+            orient,
+            fill_holes: fill,
+            max_hole_size,
+            remove_degenerate,
+            min_area,
+            min_edge_length,
+        } => {
+            let output = output(derived_path(&input, "_repaired"));
+            let unit = header_unit(&input);
+            let mut triangles = StlParser::read_stl(input)?;
+
+            if remove_degenerate {
+                let options = CleanupOptions {
+                    min_area,
+                    min_edge_length,
+                };
+                let report = remove_degenerate_facets(&mut triangles, &options);
+                println!(
+                    "removed facets: {} with repeated vertices, {} with zero area, {} duplicates, {} reversed duplicates",
+                    report.repeated_vertices,
+                    report.zero_area,
+                    report.duplicates,
+                    report.reversed_duplicates
+                );
+            }
+
+            // holes are traced along directed boundary edges, which a flipped
+            // facet would interrupt
+            if orient {
+                println!("flipped facets: {}", orient_facets(&mut triangles));
+            }
+
+            if fill {
+                let report = fill_holes(&mut triangles, max_hole_size);
+                for hole in &report.filled {
+                    println!(
+                        "filled hole: {} edges, {} facets, area {}",
+                        hole.edges, hole.facets, hole.area
+                    );
+                }
+                if report.skipped > 0 {
+                    println!(
+                        "skipped {} holes larger than {} edges",
+                        report.skipped, max_hole_size
+                    );
+                }
+            }
+
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Split { input, sort_by } => {
+            let base = output(input.clone());
+            let unit = header_unit(&input);
+            let mut components = split_components(&StlParser::read_stl(input)?);
+            if let Some(order) = sort_by {
+                sort_components(&mut components, order);
+            }
+
+            for (n, component) in components.into_iter().enumerate() {
+                let path = derived_path(&base, &format!("_{}", n + 1));
+                println!("{}: {} facets", path.display(), component.len());
+                write_stl(path, component, args.output_format, unit)?;
+            }
+        }
+        Command::Merge {
+            inputs,
+            translate,
+            weld,
+        } => {
+            if translate.len() > inputs.len() {
+                return Err(anyhow!(
+                    "Got {} offsets for {} inputs!",
+                    translate.len(),
+                    inputs.len()
+                ));
+            }
+
+            let unit = common_unit(&inputs);
+            let meshes = inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    let transform = translate.get(i).map(|&offset| Mat4::translation(offset));
+                    Ok((StlParser::read_stl(input)?, transform))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let merged = merge(meshes, weld);
+            write_stl(
+                output(PathBuf::from("Merged.stl")),
+                merged,
+                args.output_format,
+                unit,
+            )?;
+        }
+        Command::Info { input, density } => {
+            let triangles = StlParser::read_stl(input.clone())?;
+            println!("facets:            {}", triangles.len());
+            println!(
+                "units:             {}",
+                input_unit(input, &triangles).symbol()
+            );
+            print!("{}", MassProperties::new(&triangles, density));
+
+            let aabb = Aabb::from_triangles(&triangles);
+            let point = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+            println!("bounding box:");
+            println!("  min:    {}", point(aabb.min));
+            println!("  max:    {}", point(aabb.max));
+            println!("  size:   {}", point(aabb.size()));
+            println!("  center: {}", point(aabb.center()));
+
+            let obb = Obb::from_triangles(&triangles);
+            println!("oriented bounding box:");
+            println!("  center: {}", point(obb.center));
+            println!("  size:   {}", point(obb.size()));
+            for axis in obb.axes {
+                println!("  axis:   {}", point(axis));
+            }
+        }
+        Command::Transform {
+            input,
+            translate,
+            rotate,
+            scale,
+            mirror,
+            center,
+            fit,
+            drop_to_floor: drop,
+        } => {
+            let output = output(derived_path(&input, "_transformed"));
+            let unit = header_unit(&input);
+            let mut triangles = StlParser::read_stl(input)?;
+
+            if center {
+                center_on_origin(&mut triangles);
+            }
+            if let Some(size) = fit {
+                scale_to_fit(&mut triangles, size);
+            }
+
+            let mut matrix = Mat4::identity();
+            if let Some(factors) = scale {
+                matrix = Mat4::scaling(factors) * matrix;
+            }
+            if let Some(normal) = mirror {
+                if normal.d3() < EPS {
+                    return Err(anyhow!("Mirror plane normal must not be zero!"));
+                }
+                matrix = Mat4::mirror(normal) * matrix;
+            }
+            if let Some(angles) = rotate {
+                matrix = Mat4::rotation_degrees(angles) * matrix;
+            }
+            if let Some(offset) = translate {
+                matrix = Mat4::translation(offset) * matrix;
+            }
+            transform(&mut triangles, &matrix);
+
+            if drop {
+                drop_to_floor(&mut triangles);
+            }
+
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Decimate {
+            input,
+            target,
+            ratio,
+            max_error,
+        } => {
+            if target.is_none() && ratio.is_none() && max_error.is_none() {
+                return Err(anyhow!(
+                    "Either --target, --ratio or --max-error is required!"
+                ));
+            }
+            if ratio.is_some_and(|ratio| !(0.0..=1.0).contains(&ratio)) {
+                return Err(anyhow!("Ratio must be between 0 and 1!"));
+            }
+
+            let output = output(derived_path(&input, "_decimated"));
+            let unit = header_unit(&input);
+            let mut triangles = StlParser::read_stl(input)?;
+            let from_ratio = ratio.map(|ratio| (triangles.len() as f64 * ratio).round() as usize);
+            let options = DecimateOptions {
+                target_facets: target.max(from_ratio),
+                max_error,
+            };
+            let before = triangles.len();
+            decimate(&mut triangles, &options);
+            println!("facets: {} -> {}", before, triangles.len());
+
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Subdivide {
+            input,
+            scheme,
+            iterations,
+        } => {
+            let output = output(derived_path(&input, "_subdivided"));
+            let unit = header_unit(&input);
+            let mut triangles = StlParser::read_stl(input)?;
+            // every step splits each facet into four
+            let facets = triangles.len() as f64 * 4f64.powf(iterations as f64);
+            if facets > 1e8 {
+                return Err(anyhow!(
+                    "The result would have {:.0} facets, choose fewer iterations!",
+                    facets
+                ));
+            }
+            subdivide(&mut triangles, scheme, iterations);
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Smooth {
+            input,
+            method,
+            weights,
+            iterations,
+            lambda,
+            mu,
+            free_boundary,
+            feature_angle,
+        } => {
+            let output = output(derived_path(&input, "_smoothed"));
+            let unit = header_unit(&input);
+            let mut triangles = StlParser::read_stl(input)?;
+            let options = SmoothingOptions {
+                method,
+                weights,
+                iterations,
+                lambda,
+                mu,
+                pin_boundary: !free_boundary,
+                feature_angle,
+            };
+            println!("pinned vertices: {}", smooth(&mut triangles, &options));
+            write_stl(output, triangles, args.output_format, unit)?;
+        }
+        Command::Boolean { a, b, op } => {
+            let output = output(derived_path(&a, &format!("_{:?}", op).to_lowercase()));
+            let unit = common_unit(&[a.clone(), b.clone()]);
+            let mut meshes = Vec::new();
+            for input in [a, b] {
+                let triangles = StlParser::read_stl(input.clone())?;
+                if !MeshReport::new(&triangles).is_watertight() {
+                    return Err(anyhow!("{} is not a closed mesh!", input.display()));
+                }
+                meshes.push(triangles);
+            }
+
+            let result = boolean(&meshes[0], &meshes[1], op);
+            if !MeshReport::new(&result).is_watertight() {
+                return Err(anyhow!(
+                    "The result has open edges, the surfaces may be too close to coplanar!"
+                ));
+            }
+            println!("facets: {}", result.len());
+            write_stl(output, result, args.output_format, unit)?;
         }
-        Command::Cube { a, origin } => {
-            StlGenerator::generate_cube_stl(a, origin, output, args.output_format)?
+        Command::Hull { input } => {
+            let output = output(derived_path(&input, "_hull"));
+            let unit = header_unit(&input);
+            let hull = convex_hull(&StlParser::read_stl(input)?)?;
+            println!("facets: {}", hull.len());
+            println!("volume: {}", MassProperties::new(&hull, 1.0).volume);
+            write_stl(output, hull, args.output_format, unit)?;
         }
-        Command::Parse { input } => {
+        Command::Slice {
+            input,
+            layer_height,
+            height,
+            normal,
+            format,
+            split_layers,
+        } => {
+            if normal.d3() < EPS {
+                return Err(anyhow!("Slicing plane normal must not be zero!"));
+            }
+            let base = output(derived_path(&input, "_slice")).with_extension(format.extension());
             let triangles = StlParser::read_stl(input)?;
+            let layers = match (layer_height, height) {
+                (Some(layer_height), _) => slice_layers(&triangles, normal, layer_height as f64),
+                (None, Some(height)) => vec![slice(&triangles, normal, height)],
+                (None, None) => {
+                    return Err(anyhow!("Either --layer-height or --height is required!"));
+                }
+            };
+
+            for (n, layer) in layers.iter().enumerate() {
+                println!(
+                    "layer {}: height {}, {} contours, area {}",
+                    n + 1,
+                    layer.height,
+                    layer.contours.len(),
+                    layer.area()
+                );
+            }
 
-            match args.output_format {
-                StlFormat::ASCII => StlParser::write_to_ascii_file(output, triangles)?,
-                StlFormat::Binary => StlParser::write_to_binary_file(output, triangles)?,
+            if split_layers {
+                for (n, layer) in layers.iter().enumerate() {
+                    let path = derived_path(&base, &format!("_{}", n + 1))
+                        .with_extension(format.extension());
+                    write_contours(path, std::slice::from_ref(layer), format)?;
+                }
+            } else {
+                write_contours(base, &layers, format)?;
+            }
+        }
+        Command::Gcode { input, settings } => {
+            let settings = match settings {
+                Some(path) => PrintSettings::from_file(path)?,
+                None => PrintSettings::default(),
+            };
+            let path = output(derived_path(&input, "").with_extension("gcode"));
+            let triangles = StlParser::read_stl(input)?;
+            if !fits_on_bed(&triangles, &settings) {
+                return Err(anyhow!("The mesh does not fit onto the bed!"));
+            }
+            let layers = plan_layers(&triangles, &settings);
+            if layers.is_empty() {
+                return Err(anyhow!("The mesh is thinner than one layer!"));
+            }
+            let stats = write_gcode_file(path, &layers, &settings)?;
+            println!("layers: {}", stats.layers);
+            println!("filament: {:.1} mm", stats.filament);
+        }
+        Command::Voxelize {
+            input,
+            size,
+            mode,
+            format,
+        } => {
+            let path = output(derived_path(&input, "_voxels").with_extension(format.extension()));
+            let unit = header_unit(&input);
+            let triangles = StlParser::read_stl(input)?;
+            let extent = Aabb::from_triangles(&triangles).size() / size;
+            let cells = [extent.x, extent.y, extent.z]
+                .iter()
+                .map(|&n| n.ceil().max(1.0) as f64)
+                .product::<f64>();
+            if cells > 1e9 {
+                return Err(anyhow!(
+                    "The grid would have {:.0} voxels, choose a larger size!",
+                    cells
+                ));
+            }
+            let grid = voxelize(&triangles, size, mode);
+            println!(
+                "dimensions: {} x {} x {}",
+                grid.dims[0], grid.dims[1], grid.dims[2]
+            );
+            println!("voxels: {}", grid.count());
+            match format {
+                VoxelFormat::Stl => write_stl(path, grid.to_triangles(), args.output_format, unit)?,
+                _ => write_voxels(path, &grid, format)?,
+            }
+        }
+        Command::Compare {
+            a,
+            b,
+            samples,
+            seed,
+            tolerance,
+            per_vertex,
+        } => {
+            let a = StlParser::read_stl(a)?;
+            let b = StlParser::read_stl(b)?;
+            let comparison = compare(&a, &b, samples, seed);
+            for (name, deviation) in [
+                ("a -> b", comparison.forward),
+                ("b -> a", comparison.backward),
+            ] {
+                println!(
+                    "{}: max {}, mean {}, rms {}",
+                    name, deviation.max, deviation.mean, deviation.rms
+                );
+            }
+            println!("hausdorff: {}", comparison.hausdorff());
+            println!("rms:       {}", comparison.rms());
+
+            if let Some(path) = per_vertex {
+                write_deviations(path, &vertex_deviations(&a, &Bvh::new(&b)))?;
+            }
+            if comparison.hausdorff().is_infinite() {
+                return Err(anyhow!("One of the meshes has no facets!"));
+            }
+            if let Some(tolerance) = tolerance {
+                if comparison.hausdorff() > tolerance {
+                    return Err(anyhow!(
+                        "Hausdorff distance {} exceeds the tolerance {}!",
+                        comparison.hausdorff(),
+                        tolerance
+                    ));
+                }
+            }
+        }
+        Command::Diff {
+            a,
+            b,
+            tolerance,
+            json,
+        } => {
+            // first line, so that ASCII files report their solid name
+            let header = |path: &Path| {
+                StlParser::read_binary_header(path.to_path_buf())
+                    .ok()
+                    .map(|header| header.lines().next().unwrap_or_default().to_string())
+            };
+            // like diff(1): 0 when identical, 1 when different, 2 on errors
+            let read = |path: PathBuf| {
+                StlParser::read_stl(path).unwrap_or_else(|err| {
+                    eprintln!("Error: {:?}", err);
+                    std::process::exit(2)
+                })
+            };
+            let headers = [header(&a), header(&b)];
+            let diff = StlDiff::new(&read(a), &read(b), headers, tolerance);
+            if json {
+                println!("{}", diff.to_json());
+            } else {
+                print!("{}", diff);
+            }
+            if !diff.is_identical() {
+                std::process::exit(1);
             }
         }
     }
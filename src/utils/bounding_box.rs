@@ -0,0 +1,413 @@
+use super::{
+    matrix::Mat3,
+    mesh::IndexedMesh,
+    point::{cross, dot, to_f64, to_vec3, Point},
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+/// Axis-aligned bounding box. The empty box has `min > max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Aabb::empty()
+    }
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vec3::from((Float::INFINITY, Float::INFINITY, Float::INFINITY)),
+            max: Vec3::from((
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+            )),
+        }
+    }
+
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Self {
+        let mut aabb = Aabb::empty();
+        for point in points {
+            aabb.extend(point);
+        }
+        aabb
+    }
+
+    pub fn from_triangle(triangle: &Triangle) -> Self {
+        Aabb::from_points(triangle.vertices)
+    }
+
+    pub fn from_triangles(triangles: &Triangles) -> Self {
+        Aabb::from_points(triangles.iter().flat_map(|t| t.vertices))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn extend(&mut self, point: Vec3) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The overlapping region, `None` if the boxes are disjoint.
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        let aabb = Aabb {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        };
+        (!aabb.is_empty()).then_some(aabb)
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Whether `point` lies inside or on the border of the box.
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+            && self.min.z <= point.z
+            && point.z <= self.max.z
+    }
+
+    pub fn contains_box(&self, other: &Aabb) -> bool {
+        other.is_empty() || (self.contains(other.min) && self.contains(other.max))
+    }
+
+    pub fn size(&self) -> Vec3 {
+        if self.is_empty() {
+            return Vec3::from((0.0, 0.0, 0.0));
+        }
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn volume(&self) -> Float {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn surface_area(&self) -> Float {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+}
+
+/// Oriented bounding box with orthonormal, right-handed `axes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obb {
+    pub center: Vec3,
+    pub axes: [Vec3; 3],
+    pub half_extents: Vec3,
+}
+
+impl Obb {
+    /// Approximately minimal oriented box around the mesh.
+    ///
+    /// Starts from the principal axes of the vertices and then, for each
+    /// principal axis, fits the minimum-area rectangle to the convex hull of the
+    /// vertices projected along it with rotating calipers. The smallest of these
+    /// boxes and the axis-aligned box is returned.
+    pub fn from_triangles(triangles: &Triangles) -> Self {
+        let points: Vec<Point> = IndexedMesh::from_triangles(triangles)
+            .vertices
+            .into_iter()
+            .map(to_f64)
+            .collect();
+
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        if points.is_empty() {
+            return fit_box(&points, identity);
+        }
+
+        let principal = principal_axes(&points);
+        let mut best = fit_box(&points, identity);
+        let mut candidates = vec![principal];
+        for k in 0..3 {
+            if let Some(axes) = calipers_axes(&points, principal, k) {
+                candidates.push(axes);
+            }
+        }
+        for axes in candidates {
+            let candidate = fit_box(&points, axes);
+            if candidate.volume() < best.volume() {
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.half_extents * 2.0
+    }
+
+    pub fn volume(&self) -> Float {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let [a, b, c] = self.axes;
+        let h = self.half_extents;
+        std::array::from_fn(|i| {
+            let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+            self.center + a * (h.x * sign(1)) + b * (h.y * sign(2)) + c * (h.z * sign(4))
+        })
+    }
+}
+
+type Axes = [Point; 3];
+
+/// Eigenvectors of the vertex covariance, made right-handed.
+fn principal_axes(points: &[Point]) -> Axes {
+    let n = points.len() as f64;
+    let mut mean = [0.0; 3];
+    for p in points {
+        for i in 0..3 {
+            mean[i] += p[i] / n;
+        }
+    }
+
+    let mut covariance = Mat3::default();
+    for p in points {
+        let d = [p[0] - mean[0], p[1] - mean[1], p[2] - mean[2]];
+        for i in 0..3 {
+            for j in 0..3 {
+                covariance.m[i][j] += d[i] * d[j] / n;
+            }
+        }
+    }
+
+    let (_, [a, b, _]) = covariance.symmetric_eigen();
+    [a, b, cross(a, b)]
+}
+
+/// Axes whose third direction is `axes[up]` and whose other two come from the
+/// minimum-area rectangle around the projected hull.
+fn calipers_axes(points: &[Point], axes: Axes, up: usize) -> Option<Axes> {
+    let (u, v, w) = (axes[(up + 1) % 3], axes[(up + 2) % 3], axes[up]);
+    let projected: Vec<(f64, f64)> = points.iter().map(|&p| (dot(p, u), dot(p, v))).collect();
+    let hull = convex_hull_2d(projected);
+    let (e, _) = min_area_rectangle(&hull)?;
+
+    let a = [
+        u[0] * e.0 + v[0] * e.1,
+        u[1] * e.0 + v[1] * e.1,
+        u[2] * e.0 + v[2] * e.1,
+    ];
+    Some([a, cross(w, a), w])
+}
+
+fn fit_box(points: &[Point], axes: Axes) -> Obb {
+    let mut lo = [f64::INFINITY; 3];
+    let mut hi = [f64::NEG_INFINITY; 3];
+    for &p in points {
+        for k in 0..3 {
+            let t = dot(p, axes[k]);
+            lo[k] = lo[k].min(t);
+            hi[k] = hi[k].max(t);
+        }
+    }
+    if points.is_empty() {
+        (lo, hi) = ([0.0; 3], [0.0; 3]);
+    }
+
+    let mid: Point = std::array::from_fn(|k| (lo[k] + hi[k]) / 2.0);
+    let center: Point = std::array::from_fn(|i| (0..3).map(|k| axes[k][i] * mid[k]).sum::<f64>());
+
+    Obb {
+        center: to_vec3(center),
+        axes: axes.map(to_vec3),
+        half_extents: to_vec3(std::array::from_fn(|k| (hi[k] - lo[k]) / 2.0)),
+    }
+}
+
+fn orient_2d(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Andrew's monotone chain, counter-clockwise without collinear points.
+fn convex_hull_2d(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(2 * points.len());
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &(f64, f64)>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for &p in ordered {
+            while hull.len() >= start + 2
+                && orient_2d(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point of each chain starts the next one
+        hull.pop();
+    }
+    hull
+}
+
+/// Rotating calipers over a counter-clockwise hull. Returns the unit direction
+/// of the rectangle side lying on a hull edge and the rectangle area.
+fn min_area_rectangle(hull: &[(f64, f64)]) -> Option<((f64, f64), f64)> {
+    let n = hull.len();
+    if n < 3 {
+        return None;
+    }
+
+    let proj = |p: (f64, f64), d: (f64, f64)| p.0 * d.0 + p.1 * d.1;
+    let (mut right, mut top, mut left) = (0, 0, 0);
+    let mut best: Option<((f64, f64), f64)> = None;
+
+    for i in 0..n {
+        let (a, b) = (hull[i], hull[(i + 1) % n]);
+        let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let e = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        // the hull is counter-clockwise, so the interior is to the left of every edge
+        let normal = (-e.1, e.0);
+
+        if i == 0 {
+            right = (0..n)
+                .max_by(|&p, &q| proj(hull[p], e).total_cmp(&proj(hull[q], e)))
+                .unwrap();
+            top = (0..n)
+                .max_by(|&p, &q| proj(hull[p], normal).total_cmp(&proj(hull[q], normal)))
+                .unwrap();
+            left = (0..n)
+                .min_by(|&p, &q| proj(hull[p], e).total_cmp(&proj(hull[q], e)))
+                .unwrap();
+        } else {
+            for _ in 0..n {
+                if proj(hull[(right + 1) % n], e) < proj(hull[right], e) {
+                    break;
+                }
+                right = (right + 1) % n;
+            }
+            for _ in 0..n {
+                if proj(hull[(top + 1) % n], normal) < proj(hull[top], normal) {
+                    break;
+                }
+                top = (top + 1) % n;
+            }
+            for _ in 0..n {
+                if proj(hull[(left + 1) % n], e) > proj(hull[left], e) {
+                    break;
+                }
+                left = (left + 1) % n;
+            }
+        }
+
+        let width = proj(hull[right], e) - proj(hull[left], e);
+        let height = proj(hull[top], normal) - proj(a, normal);
+        let area = width * height;
+        if area < best.map_or(f64::INFINITY, |(_, best_area)| best_area) {
+            best = Some((e, area));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aabb_operations() {
+        let a = Aabb::from_points([Vec3::from((0.0, 0.0, 0.0)), Vec3::from((2.0, 2.0, 2.0))]);
+        let b = Aabb::from_points([Vec3::from((1.0, 1.0, 1.0)), Vec3::from((3.0, 4.0, 5.0))]);
+
+        assert_eq!(a.union(&b).min, Vec3::from((0.0, 0.0, 0.0)));
+        assert_eq!(a.union(&b).max, Vec3::from((3.0, 4.0, 5.0)));
+        let overlap = a.intersection(&b).unwrap();
+        assert_eq!(overlap.size(), Vec3::from((1.0, 1.0, 1.0)));
+        assert!(a.contains(Vec3::from((1.0, 2.0, 0.0))));
+        assert!(!a.contains(Vec3::from((1.0, 2.1, 0.0))));
+        assert!(a.union(&b).contains_box(&a));
+        assert!(!a.contains_box(&b));
+        assert_eq!(a.center(), Vec3::from((1.0, 1.0, 1.0)));
+
+        let far = Aabb::from_points([Vec3::from((5.0, 5.0, 5.0))]);
+        assert_eq!(a.intersection(&far), None);
+        assert!(Aabb::empty().is_empty());
+    }
+
+    #[test]
+    fn test_obb_of_rotated_box() {
+        // a 4 x 2 x 1 box rotated by 45 degrees around z
+        let (s, c) = (
+            std::f32::consts::FRAC_1_SQRT_2,
+            std::f32::consts::FRAC_1_SQRT_2,
+        );
+        let corner = |i: usize| {
+            let x = if i & 1 == 0 { -2.0 } else { 2.0 };
+            let y = if i & 2 == 0 { -1.0 } else { 1.0 };
+            let z = if i & 4 == 0 { -0.5 } else { 0.5 };
+            Vec3::from((c * x - s * y, s * x + c * y, z))
+        };
+        let triangles: Triangles = [
+            [0, 1, 3],
+            [0, 3, 2],
+            [4, 7, 5],
+            [4, 6, 7],
+            [0, 5, 1],
+            [2, 3, 7],
+        ]
+        .iter()
+        .map(|f| Triangle::from_vertices(f.map(corner)))
+        .collect();
+
+        let obb = Obb::from_triangles(&triangles);
+        assert!((obb.volume() - 8.0).abs() < 1e-3);
+        assert!(obb.volume() < Aabb::from_triangles(&triangles).volume());
+        let mut size = [obb.size().x, obb.size().y, obb.size().z];
+        size.sort_by(|a, b| a.total_cmp(b));
+        for (actual, expected) in size.iter().zip([1.0, 2.0, 4.0]) {
+            assert!((actual - expected).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_min_area_rectangle_of_square() {
+        let hull = convex_hull_2d(vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+            (0.5, 0.5),
+        ]);
+        assert_eq!(hull.len(), 4);
+        let (_, area) = min_area_rectangle(&hull).unwrap();
+        assert!((area - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,434 @@
+use super::{
+    bounding_box::Aabb,
+    point::{cross, dot, sub, to_f64, Point},
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+fn component(v: Vec3, axis: usize) -> Float {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// Half line `origin + t * direction` for `t >= 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Unit length, so that hit distances are euclidean.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, distance: Float) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+}
+
+/// Intersection of a ray with a facet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// Index into the triangles the hierarchy was built from.
+    pub facet: usize,
+    pub distance: Float,
+    /// Barycentric weights of the second and third vertex; the hit point is
+    /// `(1 - u - v) * v0 + u * v1 + v * v2`.
+    pub u: Float,
+    pub v: Float,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Aabb,
+    /// First facet of a leaf, or the left child of an inner node whose right
+    /// child follows it.
+    first: usize,
+    /// Facets of a leaf, zero for inner nodes.
+    count: usize,
+}
+
+/// Bounding volume hierarchy over the facets of a mesh.
+///
+/// Built top-down with the surface area heuristic evaluated over binned
+/// centroids. Ray tests run in `f64` with the Möller–Trumbore algorithm and
+/// count hits on edges and vertices for every facet that shares them.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Facet indices, each leaf owns a contiguous range.
+    facets: Vec<usize>,
+    triangles: Vec<[Point; 3]>,
+}
+
+const BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+
+impl Bvh {
+    pub fn new(triangles: &Triangles) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            facets: (0..triangles.len()).collect(),
+            triangles: triangles.iter().map(|t| t.vertices.map(to_f64)).collect(),
+        };
+        if triangles.is_empty() {
+            return bvh;
+        }
+
+        let boxes: Vec<Aabb> = triangles.iter().map(Aabb::from_triangle).collect();
+        let centers: Vec<Vec3> = boxes.iter().map(Aabb::center).collect();
+
+        bvh.nodes.push(Node {
+            bounds: Aabb::empty(),
+            first: 0,
+            count: triangles.len(),
+        });
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let (first, count) = (bvh.nodes[index].first, bvh.nodes[index].count);
+            let range = first..first + count;
+            let bounds = bvh.facets[range.clone()]
+                .iter()
+                .fold(Aabb::empty(), |bounds, &f| bounds.union(&boxes[f]));
+            bvh.nodes[index].bounds = bounds;
+            if count <= 1 {
+                continue;
+            }
+
+            let Some(split) = split(&mut bvh.facets[range], &boxes, &centers, &bounds) else {
+                continue;
+            };
+            let left = bvh.nodes.len();
+            bvh.nodes.push(Node {
+                bounds: Aabb::empty(),
+                first,
+                count: split,
+            });
+            bvh.nodes.push(Node {
+                bounds: Aabb::empty(),
+                first: first + split,
+                count: count - split,
+            });
+            bvh.nodes[index] = Node {
+                bounds,
+                first: left,
+                count: 0,
+            };
+            stack.extend([left, left + 1]);
+        }
+        bvh
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Box around all facets.
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::empty(), |node| node.bounds)
+    }
+
+    /// Nearest facet along the ray.
+    pub fn closest_hit(&self, ray: &Ray) -> Option<Hit> {
+        let mut closest: Option<Hit> = None;
+        self.traverse(ray, |hit| {
+            if hit.distance < closest.map_or(Float::INFINITY, |c| c.distance) {
+                closest = Some(hit);
+            }
+            closest.map_or(f64::INFINITY, |c| c.distance as f64)
+        });
+        closest
+    }
+
+    /// Every facet the ray passes through, ordered by distance.
+    pub fn all_hits(&self, ray: &Ray) -> Vec<Hit> {
+        let mut hits = Vec::new();
+        self.traverse(ray, |hit| {
+            hits.push(hit);
+            f64::INFINITY
+        });
+        hits.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then(a.facet.cmp(&b.facet))
+        });
+        hits
+    }
+
+    /// Visits the hits in nodes the ray enters before the distance returned
+    /// by `visit`.
+    fn traverse(&self, ray: &Ray, mut visit: impl FnMut(Hit) -> f64) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let origin = to_f64(ray.origin);
+        let direction = to_f64(ray.direction);
+        let inverse = direction.map(|d| 1.0 / d);
+        let mut limit = f64::INFINITY;
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            match enter(&node.bounds, origin, inverse) {
+                Some(t) if t <= limit => {}
+                _ => continue,
+            }
+            if node.count == 0 {
+                stack.extend([node.first + 1, node.first]);
+                continue;
+            }
+            for &facet in &self.facets[node.first..node.first + node.count] {
+                if let Some((t, u, v)) = intersect(&self.triangles[facet], origin, direction) {
+                    if t <= limit {
+                        limit = limit.min(visit(Hit {
+                            facet,
+                            distance: t as Float,
+                            u: u as Float,
+                            v: v as Float,
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Visits leaf facets in order of increasing box distance from `point`,
+    /// skipping nodes farther away than the distance `visit` returns.
+    pub(super) fn nearest(&self, point: Vec3, mut visit: impl FnMut(usize, &[Point; 3]) -> f64) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let point = to_f64(point);
+        let mut limit = f64::INFINITY;
+
+        let mut stack = vec![(0, box_distance(&self.nodes[0].bounds, point))];
+        while let Some((index, distance)) = stack.pop() {
+            if distance > limit {
+                continue;
+            }
+            let node = &self.nodes[index];
+            if node.count == 0 {
+                let children = [node.first, node.first + 1]
+                    .map(|child| (child, box_distance(&self.nodes[child].bounds, point)));
+                // the nearer child is popped first
+                let [near, far] = if children[0].1 <= children[1].1 {
+                    children
+                } else {
+                    [children[1], children[0]]
+                };
+                stack.extend([far, near]);
+                continue;
+            }
+            for &facet in &self.facets[node.first..node.first + node.count] {
+                limit = limit.min(visit(facet, &self.triangles[facet]));
+            }
+        }
+    }
+
+    /// The facet as it was passed in, with the normal recomputed.
+    pub fn triangle(&self, facet: usize) -> Triangle {
+        Triangle::from_vertices(
+            self.triangles[facet].map(|p| Vec3::from((p[0] as f32, p[1] as f32, p[2] as f32))),
+        )
+    }
+}
+
+/// Partitions `facets` at the cheapest binned SAH split and returns the size
+/// of the left part, or `None` when a leaf is cheaper.
+fn split(facets: &mut [usize], boxes: &[Aabb], centers: &[Vec3], bounds: &Aabb) -> Option<usize> {
+    let centroid_bounds = Aabb::from_points(facets.iter().map(|&f| centers[f]));
+    let extent = centroid_bounds.size();
+    let axis = (0..3)
+        .max_by(|&a, &b| component(extent, a).total_cmp(&component(extent, b)))
+        .unwrap();
+    let (low, width) = (
+        component(centroid_bounds.min, axis),
+        component(extent, axis),
+    );
+    if width <= 0.0 {
+        return None;
+    }
+    let bin = |f: usize| {
+        let t = (component(centers[f], axis) - low) / width;
+        ((t * BINS as Float) as usize).min(BINS - 1)
+    };
+
+    let mut bins = [(Aabb::empty(), 0usize); BINS];
+    for &f in facets.iter() {
+        let b = &mut bins[bin(f)];
+        b.0 = b.0.union(&boxes[f]);
+        b.1 += 1;
+    }
+
+    // cost of putting bins 0..=i on the left
+    let mut left = [(0.0, 0); BINS - 1];
+    let mut acc = (Aabb::empty(), 0);
+    for i in 0..BINS - 1 {
+        acc = (acc.0.union(&bins[i].0), acc.1 + bins[i].1);
+        left[i] = (acc.0.surface_area() as f64, acc.1);
+    }
+    let mut best: Option<(f64, usize)> = None;
+    let mut acc = (Aabb::empty(), 0);
+    for i in (0..BINS - 1).rev() {
+        acc = (acc.0.union(&bins[i + 1].0), acc.1 + bins[i + 1].1);
+        let (area, count) = left[i];
+        if count == 0 || acc.1 == 0 {
+            continue;
+        }
+        let cost = area * count as f64 + acc.0.surface_area() as f64 * acc.1 as f64;
+        if cost < best.map_or(f64::INFINITY, |(c, _)| c) {
+            best = Some((cost, i));
+        }
+    }
+
+    let (cost, i) = best?;
+    let area = bounds.surface_area() as f64;
+    let leaf_cost = facets.len() as f64;
+    if facets.len() <= MAX_LEAF_SIZE && (area <= 0.0 || 1.0 + cost / area >= leaf_cost) {
+        return None;
+    }
+
+    let mut split = 0;
+    for j in 0..facets.len() {
+        if bin(facets[j]) <= i {
+            facets.swap(j, split);
+            split += 1;
+        }
+    }
+    Some(split)
+}
+
+/// Distance at which the ray enters the box, slab test.
+fn enter(bounds: &Aabb, origin: Point, inverse: Point) -> Option<f64> {
+    let (min, max) = (to_f64(bounds.min), to_f64(bounds.max));
+    let (mut near, mut far) = (0.0f64, f64::INFINITY);
+    for i in 0..3 {
+        let (a, b) = (
+            (min[i] - origin[i]) * inverse[i],
+            (max[i] - origin[i]) * inverse[i],
+        );
+        // 0 * inf is NaN for rays in the plane of a slab; max and min skip it
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    (near <= far).then_some(near)
+}
+
+/// Euclidean distance from `point` to the box, zero inside.
+fn box_distance(bounds: &Aabb, point: Point) -> f64 {
+    let (min, max) = (to_f64(bounds.min), to_f64(bounds.max));
+    let d: Point = std::array::from_fn(|i| (min[i] - point[i]).max(point[i] - max[i]).max(0.0));
+    dot(d, d).sqrt()
+}
+
+/// Möller–Trumbore, returns distance and barycentrics. Hits on the border of
+/// the facet count.
+fn intersect(triangle: &[Point; 3], origin: Point, direction: Point) -> Option<(f64, f64, f64)> {
+    let e1 = sub(triangle[1], triangle[0]);
+    let e2 = sub(triangle[2], triangle[0]);
+    let p = cross(direction, e2);
+    let det = dot(e1, p);
+    if det.abs() <= f64::EPSILON * dot(e1, e1).sqrt() * dot(e2, e2).sqrt() {
+        return None;
+    }
+    let s = sub(origin, triangle[0]);
+    let u = dot(s, p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross(s, e1);
+    let v = dot(direction, q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = dot(e2, q) / det;
+    (t >= 0.0).then_some((t, u, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    #[test]
+    fn test_closest_hit() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 2.0, 3.0).into());
+        let bvh = Bvh::new(&triangles);
+        assert_eq!(bvh.bounds(), Aabb::from_triangles(&triangles));
+
+        let ray = Ray::new((0.3, 0.6, -2.0).into(), (0.0, 0.0, 2.0).into());
+        let hit = bvh.closest_hit(&ray).unwrap();
+        assert!((hit.distance - 2.0).abs() < 1e-6);
+        assert!(triangles[hit.facet].normal.z < 0.0);
+
+        let [a, b, c] = triangles[hit.facet].vertices;
+        let point = a * (1.0 - hit.u - hit.v) + b * hit.u + c * hit.v;
+        assert!((point - ray.at(hit.distance)).d3() < 1e-6);
+
+        let miss = Ray::new((2.0, 0.5, 0.5).into(), (1.0, 0.0, 0.0).into());
+        assert_eq!(bvh.closest_hit(&miss), None);
+        assert_eq!(Bvh::new(&Triangles::new()).closest_hit(&ray), None);
+    }
+
+    #[test]
+    fn test_all_hits_match_brute_force() {
+        let mut triangles = Triangles::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let min = Vec3::from((i as f32 * 1.5, j as f32 * 1.5, (i + j) as f32 * 0.1));
+                triangles.extend(cuboid(min, min + Vec3::from((1.0, 1.0, 1.0))));
+            }
+        }
+        let bvh = Bvh::new(&triangles);
+
+        let mut seed = 12345u32;
+        let mut random = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32
+        };
+        for _ in 0..200 {
+            let origin = Vec3::from((random() * 15.0, random() * 15.0, -1.0));
+            let direction = Vec3::from((random() - 0.5, random() - 0.5, 1.0));
+            let ray = Ray::new(origin, direction);
+
+            let hits = bvh.all_hits(&ray);
+            let expected = bvh
+                .triangles
+                .iter()
+                .filter(|t| intersect(t, to_f64(ray.origin), to_f64(ray.direction)).is_some())
+                .count();
+            assert_eq!(hits.len(), expected);
+            assert!(hits.windows(2).all(|w| w[0].distance <= w[1].distance));
+            if let Some(first) = hits.first() {
+                assert_eq!(bvh.closest_hit(&ray).unwrap().distance, first.distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ray_through_box() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let bvh = Bvh::new(&triangles);
+        let ray = Ray::new((0.2, 0.7, 0.4).into(), (1.0, 0.1, 0.05).into());
+        let hits = bvh.all_hits(&ray);
+        assert_eq!(hits.len(), 1);
+        assert!(triangles[hits[0].facet].normal.x > 0.0);
+
+        let outside = Ray::new((-1.0, 0.7, 0.4).into(), (1.0, 0.1, 0.05).into());
+        assert_eq!(bvh.all_hits(&outside).len(), 2);
+    }
+}
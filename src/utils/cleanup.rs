@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use super::{mesh::IndexedMesh, stl_parser::Triangles, vec3::Float, EPS};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CleanupOptions {
+    /// Facets with a smaller area are removed as slivers.
+    pub min_area: Float,
+    /// Vertices closer than this are treated as the same vertex.
+    pub min_edge_length: Float,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        CleanupOptions {
+            min_area: EPS,
+            min_edge_length: EPS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Facets with a collapsed edge.
+    pub repeated_vertices: usize,
+    /// Facets with all vertices distinct but (nearly) zero area.
+    pub zero_area: usize,
+    /// Facets identical to an earlier one.
+    pub duplicates: usize,
+    /// Facets identical to an earlier one up to winding.
+    pub reversed_duplicates: usize,
+}
+
+impl CleanupReport {
+    pub fn removed(&self) -> usize {
+        self.repeated_vertices + self.zero_area + self.duplicates + self.reversed_duplicates
+    }
+}
+
+/// Removes degenerate facets and duplicates, keeping the first copy of each facet.
+///
+/// Vertices closer than `min_edge_length` are collapsed into one before the
+/// facets are compared, so near-duplicates are caught as well. The remaining
+/// facets keep the collapsed positions, with their normals recomputed.
+pub fn remove_degenerate_facets(
+    triangles: &mut Triangles,
+    options: &CleanupOptions,
+) -> CleanupReport {
+    let mesh = IndexedMesh::weld(triangles, options.min_edge_length);
+    let mut report = CleanupReport::default();
+    let mut seen: HashSet<[usize; 3]> = HashSet::new();
+
+    let keep: Vec<bool> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            if IndexedMesh::is_degenerate(face) {
+                report.repeated_vertices += 1;
+                return false;
+            }
+
+            let [a, b, c] = face.map(|i| mesh.vertices[i]);
+            if (b - a).cross(c - a).d3() / 2.0 < options.min_area {
+                report.zero_area += 1;
+                return false;
+            }
+
+            let forward = canonical(*face);
+            if seen.contains(&forward) {
+                report.duplicates += 1;
+                return false;
+            }
+            if seen.contains(&canonical([face[0], face[2], face[1]])) {
+                report.reversed_duplicates += 1;
+                return false;
+            }
+            seen.insert(forward);
+            true
+        })
+        .collect();
+
+    // the welded positions are written back, so that the neighbours of a
+    // collapsed edge stay connected
+    let mut faces = mesh.faces.iter().zip(keep);
+    triangles.retain_mut(|triangle| {
+        let (face, keep) = faces.next().unwrap();
+        triangle.vertices = face.map(|i| mesh.vertices[i]);
+        triangle.normal = triangle.unit_normal();
+        keep
+    });
+    report
+}
+
+/// Rotates the face so that its smallest index comes first, preserving the winding.
+fn canonical(face: [usize; 3]) -> [usize; 3] {
+    let first = (0..3).min_by_key(|&i| face[i]).unwrap();
+    [face[first], face[(first + 1) % 3], face[(first + 2) % 3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{mesh_report::MeshReport, stl_parser::Triangle, vec3::Vec3};
+
+    #[test]
+    fn test_remove_degenerate_facets() {
+        let a = Vec3::from((0.0, 0.0, 0.0));
+        let b = Vec3::from((1.0, 0.0, 0.0));
+        let c = Vec3::from((0.0, 1.0, 0.0));
+        let mut triangles = vec![
+            Triangle::from_vertices([a, b, c]),
+            Triangle::from_vertices([b, c, a]),
+            Triangle::from_vertices([a, c, b]),
+            Triangle::from_vertices([a, a, c]),
+            Triangle::from_vertices([a, b, b * 2.0]),
+        ];
+
+        let report = remove_degenerate_facets(&mut triangles, &CleanupOptions::default());
+        assert_eq!(
+            report,
+            CleanupReport {
+                repeated_vertices: 1,
+                zero_area: 1,
+                duplicates: 1,
+                reversed_duplicates: 1,
+            }
+        );
+        assert_eq!(report.removed(), 4);
+        assert_eq!(triangles.len(), 1);
+    }
+
+    #[test]
+    fn test_collapsed_sliver_keeps_mesh_closed() {
+        // tetrahedron whose apex is split into two close points, joined by
+        // two slivers
+        let a = Vec3::from((0.0, 0.0, 0.0));
+        let b = Vec3::from((1.0, 0.0, 0.0));
+        let c = Vec3::from((0.0, 1.0, 0.0));
+        let d1 = Vec3::from((0.0, 0.0, 1.0));
+        let d2 = Vec3::from((0.001, 0.0, 1.0));
+        let mut triangles = vec![
+            Triangle::from_vertices([a, c, b]),
+            Triangle::from_vertices([a, b, d1]),
+            Triangle::from_vertices([b, c, d2]),
+            Triangle::from_vertices([c, a, d1]),
+            Triangle::from_vertices([d1, b, d2]),
+            Triangle::from_vertices([c, d1, d2]),
+        ];
+        assert!(MeshReport::new(&triangles).is_watertight());
+
+        let options = CleanupOptions {
+            min_edge_length: 0.01,
+            ..Default::default()
+        };
+        let report = remove_degenerate_facets(&mut triangles, &options);
+        assert_eq!(report.repeated_vertices, 2);
+        assert_eq!(triangles.len(), 4);
+        assert!(MeshReport::new(&triangles).is_watertight());
+        for triangle in &triangles {
+            assert!((triangle.normal - triangle.unit_normal()).d3() < 1e-6);
+        }
+    }
+}
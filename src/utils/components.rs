@@ -0,0 +1,95 @@
+use super::{
+    mesh::{DisjointSet, IndexedMesh},
+    stl_parser::Triangles,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ComponentOrder {
+    /// largest enclosed volume first
+    Volume,
+    /// most facets first
+    Triangles,
+}
+
+/// Splits the mesh into parts that do not share any vertex.
+///
+/// Components are returned in the order of their first facet.
+pub fn split_components(triangles: &Triangles) -> Vec<Triangles> {
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let mut set = DisjointSet::new(mesh.vertices.len());
+    for face in &mesh.faces {
+        set.union(face[0], face[1]);
+        set.union(face[1], face[2]);
+    }
+
+    let mut component_of_root = vec![usize::MAX; mesh.vertices.len()];
+    let mut components: Vec<Triangles> = Vec::new();
+    for (triangle, face) in triangles.iter().zip(&mesh.faces) {
+        let root = set.find(face[0]);
+        if component_of_root[root] == usize::MAX {
+            component_of_root[root] = components.len();
+            components.push(Triangles::new());
+        }
+        components[component_of_root[root]].push(*triangle);
+    }
+    components
+}
+
+/// Sorts components in descending order of volume or facet count.
+pub fn sort_components(components: &mut [Triangles], order: ComponentOrder) {
+    match order {
+        ComponentOrder::Volume => components.sort_by(|a, b| volume(b).total_cmp(&volume(a))),
+        ComponentOrder::Triangles => components.sort_by_key(|c| std::cmp::Reverse(c.len())),
+    }
+}
+
+fn volume(triangles: &Triangles) -> f64 {
+    triangles
+        .iter()
+        .map(|t| t.signed_volume() as f64)
+        .sum::<f64>()
+        .abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::{cuboid, tetrahedron};
+
+    #[test]
+    fn test_disjoint_parts() {
+        let mut triangles = tetrahedron(0.0);
+        triangles.extend(tetrahedron(3.0));
+        let components = split_components(&triangles);
+        assert_eq!(components.len(), 2);
+        assert!(components.iter().all(|c| c.len() == 4));
+        assert_eq!(components[1][0].vertices[0].x, 3.0);
+    }
+
+    #[test]
+    fn test_touching_at_a_vertex() {
+        // the second tetrahedron starts at the corner (1, 0, 0) of the first
+        let mut triangles = tetrahedron(0.0);
+        triangles.extend(tetrahedron(1.0));
+        let components = split_components(&triangles);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), 8);
+    }
+
+    #[test]
+    fn test_sort_components() {
+        // more facets but less volume than the tetrahedron
+        let mut triangles = cuboid((5.0, 0.0, 0.0).into(), (5.1, 0.1, 0.1).into());
+        triangles.extend(tetrahedron(0.0));
+        let mut components = split_components(&triangles);
+        assert_eq!(components[0].len(), 12);
+
+        sort_components(&mut components, ComponentOrder::Volume);
+        assert_eq!(components[0].len(), 4);
+        assert_eq!(components[1].len(), 12);
+
+        sort_components(&mut components, ComponentOrder::Triangles);
+        assert_eq!(components[0].len(), 12);
+        assert_eq!(components[1].len(), 4);
+    }
+}
@@ -0,0 +1,249 @@
+use std::f64::consts::PI;
+
+use super::{
+    bvh::{Bvh, Ray},
+    point::{cross, dot, sub, to_f64, to_vec3, Point},
+    vec3::{Float, Vec3},
+};
+
+/// Ray directions for the parity vote, chosen away from the coordinate axes
+/// and diagonals that meshes tend to align with.
+const DIRECTIONS: [(Float, Float, Float); 5] = [
+    (0.34, 0.81, 0.47),
+    (-0.62, 0.29, -0.73),
+    (0.91, -0.35, 0.22),
+    (-0.18, -0.44, 0.88),
+    (0.57, -0.67, -0.48),
+];
+
+/// Nearest point of the surface to a query point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosestPoint {
+    pub facet: usize,
+    pub point: Vec3,
+    pub distance: Float,
+    /// Barycentric weights of the second and third vertex of the facet, as in
+    /// [`Hit`](super::bvh::Hit).
+    pub u: Float,
+    pub v: Float,
+}
+
+/// Whether `point` lies inside the closed mesh. Points on the surface count as
+/// inside.
+///
+/// Rays are cast in several fixed directions and each one votes with the
+/// parity of its crossings. Rays that touch an edge or a vertex do not vote;
+/// if no majority remains, the generalized winding number decides.
+pub fn contains(bvh: &Bvh, point: Vec3) -> bool {
+    let touching = bvh.bounds().size().d3() * 1e-6;
+    let (mut inside, mut outside) = (0, 0);
+    for direction in DIRECTIONS {
+        let hits = bvh.all_hits(&Ray::new(point, direction.into()));
+        if hits.first().is_some_and(|hit| hit.distance <= touching) {
+            return true;
+        }
+        if hits
+            .iter()
+            .any(|hit| hit.u.min(hit.v).min(1.0 - hit.u - hit.v) < 1e-6)
+        {
+            continue;
+        }
+        if hits.len() % 2 == 1 {
+            inside += 1;
+        } else {
+            outside += 1;
+        }
+        if inside > DIRECTIONS.len() / 2 || outside > DIRECTIONS.len() / 2 {
+            break;
+        }
+    }
+
+    if inside == outside {
+        winding_number(bvh, point) > 0.5
+    } else {
+        inside > outside
+    }
+}
+
+/// Sum of the solid angles of the facets seen from `point` over `4 pi`.
+///
+/// One inside and zero outside a closed, outward oriented mesh; for meshes
+/// with holes it degrades gracefully towards fractional values.
+pub fn winding_number(bvh: &Bvh, point: Vec3) -> f64 {
+    let p = to_f64(point);
+    let total: f64 = (0..bvh.len())
+        .map(|facet| {
+            let [a, b, c] = bvh.triangle(facet).vertices.map(|v| sub(to_f64(v), p));
+            let (la, lb, lc) = (dot(a, a).sqrt(), dot(b, b).sqrt(), dot(c, c).sqrt());
+            let numerator = dot(a, cross(b, c));
+            let denominator = la * lb * lc + dot(a, b) * lc + dot(a, c) * lb + dot(b, c) * la;
+            2.0 * numerator.atan2(denominator)
+        })
+        .sum();
+    total / (4.0 * PI)
+}
+
+/// Nearest surface point, `None` for an empty mesh.
+pub fn closest_point(bvh: &Bvh, point: Vec3) -> Option<ClosestPoint> {
+    let p = to_f64(point);
+    let mut closest: Option<(usize, Point, f64, f64, f64)> = None;
+    bvh.nearest(point, |facet, triangle| {
+        let (q, u, v) = closest_on_triangle(p, triangle);
+        let d = sub(q, p);
+        let distance = dot(d, d).sqrt();
+        if distance < closest.map_or(f64::INFINITY, |c| c.2) {
+            closest = Some((facet, q, distance, u, v));
+        }
+        closest.map_or(f64::INFINITY, |c| c.2)
+    });
+    closest.map(|(facet, q, distance, u, v)| ClosestPoint {
+        facet,
+        point: to_vec3(q),
+        distance: distance as Float,
+        u: u as Float,
+        v: v as Float,
+    })
+}
+
+/// Distance to the surface, negative inside the mesh and infinite for an
+/// empty one.
+pub fn signed_distance(bvh: &Bvh, point: Vec3) -> Float {
+    let Some(closest) = closest_point(bvh, point) else {
+        return Float::INFINITY;
+    };
+    if contains(bvh, point) {
+        -closest.distance
+    } else {
+        closest.distance
+    }
+}
+
+/// Closest point of the triangle and its barycentric weights of `b` and `c`,
+/// by the Voronoi regions of vertices, edges and face (Ericson, Real-Time
+/// Collision Detection, 5.1.5).
+fn closest_on_triangle(p: Point, [a, b, c]: &[Point; 3]) -> (Point, f64, f64) {
+    let at = |u: f64, v: f64| -> Point {
+        std::array::from_fn(|i| a[i] + u * (b[i] - a[i]) + v * (c[i] - a[i]))
+    };
+    let ratio = |n: f64, d: f64| if d > 0.0 { n / d } else { 0.0 };
+
+    let (ab, ac, ap) = (sub(*b, *a), sub(*c, *a), sub(p, *a));
+    let (d1, d2) = (dot(ab, ap), dot(ac, ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (*a, 0.0, 0.0);
+    }
+
+    let bp = sub(p, *b);
+    let (d3, d4) = (dot(ab, bp), dot(ac, bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return (*b, 1.0, 0.0);
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let u = ratio(d1, d1 - d3);
+        return (at(u, 0.0), u, 0.0);
+    }
+
+    let cp = sub(p, *c);
+    let (d5, d6) = (dot(ab, cp), dot(ac, cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return (*c, 0.0, 1.0);
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let v = ratio(d2, d2 - d6);
+        return (at(0.0, v), 0.0, v);
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let v = ratio(d4 - d3, (d4 - d3) + (d5 - d6));
+        return (at(1.0 - v, v), 1.0 - v, v);
+    }
+
+    let denominator = va + vb + vc;
+    let (u, v) = (ratio(vb, denominator), ratio(vc, denominator));
+    (at(u, v), u, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixtures::cuboid, stl_parser::Triangles};
+
+    /// Unit box with a cavity from 0.25 to 0.75.
+    fn hollow_box() -> Triangles {
+        let mut triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let mut cavity = cuboid((0.25, 0.25, 0.25).into(), (0.75, 0.75, 0.75).into());
+        cavity.iter_mut().for_each(|t| t.flip());
+        triangles.extend(cavity);
+        triangles
+    }
+
+    #[test]
+    fn test_contains() {
+        let bvh = Bvh::new(&hollow_box());
+        for i in 0..=10 {
+            for j in 0..=10 {
+                for k in 0..=10 {
+                    let p = Vec3::from((
+                        i as f32 * 0.1 + 0.03,
+                        j as f32 * 0.1 + 0.02,
+                        k as f32 * 0.1 + 0.07,
+                    ));
+                    let in_cavity = [p.x, p.y, p.z].iter().all(|c| (0.25..=0.75).contains(c));
+                    let in_box = [p.x, p.y, p.z].iter().all(|c| (0.0..=1.0).contains(c));
+                    assert_eq!(contains(&bvh, p), in_box && !in_cavity, "{:?}", p);
+                }
+            }
+        }
+        assert!((winding_number(&bvh, (0.1, 0.1, 0.1).into()) - 1.0).abs() < 1e-9);
+        assert!(winding_number(&bvh, (0.5, 0.5, 0.5).into()).abs() < 1e-9);
+        assert!(winding_number(&bvh, (3.0, 0.5, 0.5).into()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_signed_distance() {
+        let bvh = Bvh::new(&cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into()));
+        assert!((signed_distance(&bvh, (0.5, 0.5, 0.5).into()) + 0.5).abs() < 1e-6);
+        assert!((signed_distance(&bvh, (0.5, 0.9, 0.5).into()) + 0.1).abs() < 1e-6);
+        assert!((signed_distance(&bvh, (2.0, 0.5, 0.5).into()) - 1.0).abs() < 1e-6);
+        assert!((signed_distance(&bvh, (2.0, 2.0, 2.0).into()) - 3.0f32.sqrt()).abs() < 1e-6);
+        assert_eq!(
+            signed_distance(&Bvh::new(&Triangles::new()), Vec3::from((0.0, 0.0, 0.0))),
+            Float::INFINITY
+        );
+    }
+
+    #[test]
+    fn test_closest_point_matches_brute_force() {
+        let triangles = hollow_box();
+        let bvh = Bvh::new(&triangles);
+        let mut seed = 7u32;
+        let mut random = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 * 2.0 - 0.5
+        };
+        for _ in 0..200 {
+            let p = Vec3::from((random(), random(), random()));
+            let closest = closest_point(&bvh, p).unwrap();
+            let expected = triangles
+                .iter()
+                .map(|t| {
+                    let (q, _, _) = closest_on_triangle(to_f64(p), &t.vertices.map(to_f64));
+                    let d = sub(q, to_f64(p));
+                    dot(d, d).sqrt()
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert!((closest.distance as f64 - expected).abs() < 1e-6);
+
+            let [a, b, c] = triangles[closest.facet].vertices;
+            let point = a * (1.0 - closest.u - closest.v) + b * closest.u + c * closest.v;
+            assert!((point - closest.point).d3() < 1e-6);
+        }
+    }
+}
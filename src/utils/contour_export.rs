@@ -0,0 +1,403 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+
+use super::slicer::{Layer, Point2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ContourFormat {
+    /// SVG with one group per layer
+    Svg,
+    /// AutoCAD 2000 DXF with one DXF layer per slice
+    Dxf,
+}
+
+impl ContourFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ContourFormat::Svg => "svg",
+            ContourFormat::Dxf => "dxf",
+        }
+    }
+}
+
+/// Writes the contours of `layers` into a single file.
+pub fn write_contours(
+    path: PathBuf,
+    layers: &[Layer],
+    format: ContourFormat,
+) -> anyhow::Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path.clone())
+        .with_context(|| {
+            format!(
+                "Failed to create a file for writing!\nPath: \'{}\'",
+                path.display()
+            )
+        })?;
+    let mut writer = BufWriter::new(file);
+    match format {
+        ContourFormat::Svg => write_svg(&mut writer, layers)?,
+        ContourFormat::Dxf => write_dxf(&mut writer, layers)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// SVG in millimetres with the y axis pointing up like in the slicing plane.
+///
+/// Every layer becomes a group that Inkscape treats as a layer. Closed
+/// contours are filled with the even-odd rule so that holes stay open; open
+/// polylines are only stroked.
+pub fn write_svg<W: Write>(writer: &mut W, layers: &[Layer]) -> anyhow::Result<()> {
+    let points = layers
+        .iter()
+        .flat_map(|layer| &layer.contours)
+        .flat_map(|contour| &contour.points);
+    let (min, max) = points.fold(
+        ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]),
+        |(min, max), p| {
+            (
+                [min[0].min(p[0]), min[1].min(p[1])],
+                [max[0].max(p[0]), max[1].max(p[1])],
+            )
+        },
+    );
+    let (min, max) = if min[0] > max[0] {
+        ([0.0; 2], [0.0; 2])
+    } else {
+        (min, max)
+    };
+    let (width, height) = (max[0] - min[0], max[1] - min[1]);
+    // flip y so that the drawing is not mirrored
+    let map = |p: &Point2| [p[0] - min[0], max[1] - p[1]];
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    for (i, layer) in layers.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"  <g id="layer-{}" inkscape:groupmode="layer" inkscape:label="{}" fill="none" stroke="black" stroke-width="0.1">"#,
+            i + 1,
+            layer.height
+        )?;
+        for contour in &layer.contours {
+            let mut path = String::new();
+            for (j, p) in contour.points.iter().map(map).enumerate() {
+                let command = if j == 0 { 'M' } else { 'L' };
+                path += &format!("{}{} {} ", command, p[0], p[1]);
+            }
+            if contour.closed {
+                path += "Z";
+                writeln!(
+                    writer,
+                    r#"    <path d="{}" fill="black" fill-opacity="0.2" fill-rule="evenodd"/>"#,
+                    path.trim_end()
+                )?;
+            } else {
+                writeln!(writer, r#"    <path d="{}"/>"#, path.trim_end())?;
+            }
+        }
+        writeln!(writer, "  </g>")?;
+    }
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+/// DXF in the AutoCAD 2000 (AC1015) dialect with one `LWPOLYLINE` per
+/// contour, closed through flag 70.
+///
+/// Layers go to DXF layers `SLICE_1`, `SLICE_2`, ... with the layer height as
+/// elevation. Besides the entities, the file carries the tables, blocks and
+/// objects that AutoCAD requires of an AC1015 drawing.
+pub fn write_dxf<W: Write>(writer: &mut W, layers: &[Layer]) -> anyhow::Result<()> {
+    let names: Vec<String> = (1..=layers.len()).map(|i| format!("SLICE_{}", i)).collect();
+    let mut dxf = Dxf::default();
+
+    dxf.section("CLASSES");
+    dxf.group(0, "ENDSEC");
+
+    dxf.section("TABLES");
+    dxf.table("VPORT", |_, _| {});
+    dxf.table("LTYPE", |dxf, table| {
+        for name in ["ByBlock", "ByLayer", "Continuous"] {
+            dxf.record("LTYPE", table, "AcDbLinetypeTableRecord");
+            dxf.group(2, name);
+            dxf.group(70, "0");
+            dxf.group(
+                3,
+                if name == "Continuous" {
+                    "Solid line"
+                } else {
+                    ""
+                },
+            );
+            dxf.group(72, "65");
+            dxf.group(73, "0");
+            dxf.group(40, "0.0");
+        }
+    });
+    dxf.table("LAYER", |dxf, table| {
+        for name in std::iter::once("0").chain(names.iter().map(String::as_str)) {
+            dxf.record("LAYER", table, "AcDbLayerTableRecord");
+            dxf.group(2, name);
+            dxf.group(70, "0");
+            dxf.group(62, "7");
+            dxf.group(6, "Continuous");
+        }
+    });
+    dxf.table("STYLE", |dxf, table| {
+        dxf.record("STYLE", table, "AcDbTextStyleTableRecord");
+        dxf.group(2, "Standard");
+        dxf.group(70, "0");
+        dxf.group(40, "0.0");
+        dxf.group(41, "1.0");
+        dxf.group(50, "0.0");
+        dxf.group(71, "0");
+        dxf.group(42, "2.5");
+        dxf.group(3, "txt");
+        dxf.group(4, "");
+    });
+    dxf.table("VIEW", |_, _| {});
+    dxf.table("UCS", |_, _| {});
+    dxf.table("APPID", |dxf, table| {
+        dxf.record("APPID", table, "AcDbRegAppTableRecord");
+        dxf.group(2, "ACAD");
+        dxf.group(70, "0");
+    });
+    dxf.table("DIMSTYLE", |dxf, table| {
+        dxf.group(100, "AcDbDimStyleTable");
+        dxf.group(0, "DIMSTYLE");
+        let handle = dxf.handle();
+        dxf.group(105, &handle);
+        dxf.group(330, table);
+        dxf.group(100, "AcDbSymbolTableRecord");
+        dxf.group(100, "AcDbDimStyleTableRecord");
+        dxf.group(2, "Standard");
+        dxf.group(70, "0");
+    });
+    let mut block_records = Vec::new();
+    dxf.table("BLOCK_RECORD", |dxf, table| {
+        for name in ["*Model_Space", "*Paper_Space"] {
+            block_records.push(dxf.record("BLOCK_RECORD", table, "AcDbBlockTableRecord"));
+            dxf.group(2, name);
+        }
+    });
+    dxf.group(0, "ENDSEC");
+
+    dxf.section("BLOCKS");
+    for (name, owner) in ["*Model_Space", "*Paper_Space"].iter().zip(&block_records) {
+        dxf.entity("BLOCK", owner, "0");
+        dxf.group(100, "AcDbBlockBegin");
+        dxf.group(2, name);
+        dxf.group(70, "0");
+        dxf.point(10, [0.0, 0.0, 0.0]);
+        dxf.group(3, name);
+        dxf.group(1, "");
+        dxf.entity("ENDBLK", owner, "0");
+        dxf.group(100, "AcDbBlockEnd");
+    }
+    dxf.group(0, "ENDSEC");
+
+    dxf.section("ENTITIES");
+    for (layer, name) in layers.iter().zip(&names) {
+        for contour in &layer.contours {
+            dxf.entity("LWPOLYLINE", &block_records[0], name);
+            dxf.group(100, "AcDbPolyline");
+            dxf.group(90, &contour.points.len().to_string());
+            dxf.group(70, if contour.closed { "1" } else { "0" });
+            dxf.group(38, &layer.height.to_string());
+            for p in &contour.points {
+                dxf.group(10, &p[0].to_string());
+                dxf.group(20, &p[1].to_string());
+            }
+        }
+    }
+    dxf.group(0, "ENDSEC");
+
+    dxf.section("OBJECTS");
+    let root = dxf.handle();
+    let groups = dxf.handle();
+    dxf.group(0, "DICTIONARY");
+    dxf.group(5, &root);
+    dxf.group(330, "0");
+    dxf.group(100, "AcDbDictionary");
+    dxf.group(281, "1");
+    dxf.group(3, "ACAD_GROUP");
+    dxf.group(350, &groups);
+    dxf.group(0, "DICTIONARY");
+    dxf.group(5, &groups);
+    dxf.group(330, &root);
+    dxf.group(100, "AcDbDictionary");
+    dxf.group(281, "1");
+    dxf.group(0, "ENDSEC");
+    dxf.group(0, "EOF");
+
+    // the header needs the next free handle, so it goes out last
+    let mut header = Dxf::default();
+    header.section("HEADER");
+    header.group(9, "$ACADVER");
+    header.group(1, "AC1015");
+    header.group(9, "$HANDSEED");
+    header.group(5, &dxf.handle());
+    header.group(9, "$INSUNITS");
+    header.group(70, "4");
+    header.group(0, "ENDSEC");
+
+    writer.write_all(header.text.as_bytes())?;
+    writer.write_all(dxf.text.as_bytes())?;
+    Ok(())
+}
+
+/// Group codes of a DXF file in the making, with the handles handed out so
+/// far.
+#[derive(Default)]
+struct Dxf {
+    text: String,
+    handles: u32,
+}
+
+impl Dxf {
+    fn group(&mut self, code: u16, value: &str) {
+        self.text += &format!("{}\n{}\n", code, value);
+    }
+
+    fn point(&mut self, code: u16, p: [f64; 3]) {
+        for (i, c) in p.iter().enumerate() {
+            self.group(code + 10 * i as u16, &c.to_string());
+        }
+    }
+
+    fn handle(&mut self) -> String {
+        self.handles += 1;
+        format!("{:X}", self.handles)
+    }
+
+    fn section(&mut self, name: &str) {
+        self.group(0, "SECTION");
+        self.group(2, name);
+    }
+
+    /// Symbol table whose records are written by `records`, which gets the
+    /// handle of the table as owner.
+    fn table(&mut self, name: &str, records: impl FnOnce(&mut Self, &str)) {
+        let handle = self.handle();
+        self.group(0, "TABLE");
+        self.group(2, name);
+        self.group(5, &handle);
+        self.group(330, "0");
+        self.group(100, "AcDbSymbolTable");
+        self.group(70, "0");
+        records(self, &handle);
+        self.group(0, "ENDTAB");
+    }
+
+    /// Starts a table record and returns its handle.
+    fn record(&mut self, kind: &str, table: &str, subclass: &str) -> String {
+        let handle = self.handle();
+        self.group(0, kind);
+        self.group(5, &handle);
+        self.group(330, table);
+        self.group(100, "AcDbSymbolTableRecord");
+        self.group(100, subclass);
+        handle
+    }
+
+    fn entity(&mut self, kind: &str, owner: &str, layer: &str) {
+        let handle = self.handle();
+        self.group(0, kind);
+        self.group(5, &handle);
+        self.group(330, owner);
+        self.group(100, "AcDbEntity");
+        self.group(8, layer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{slicer::Contour, vec3::Vec3};
+
+    fn layer() -> Layer {
+        let square = |size: f64, hole: bool| {
+            let mut points = vec![[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]];
+            if hole {
+                points.reverse();
+            }
+            Contour {
+                points,
+                closed: true,
+                is_hole: hole,
+                parent: None,
+            }
+        };
+        Layer {
+            height: 0.5,
+            origin: Vec3::from((0.0, 0.0, 0.5)),
+            u: Vec3::from((1.0, 0.0, 0.0)),
+            v: Vec3::from((0.0, 1.0, 0.0)),
+            contours: vec![square(2.0, false), square(1.0, true)],
+        }
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut buffer = Vec::new();
+        write_svg(&mut buffer, &[layer(), layer()]).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+
+        assert!(svg.contains(r#"width="2mm" height="2mm" viewBox="0 0 2 2""#));
+        assert_eq!(svg.matches("<g ").count(), 2);
+        assert_eq!(svg.matches("<path ").count(), 4);
+        // y is flipped
+        assert!(svg.contains(r#"d="M0 2 L2 2 L2 0 L0 0 Z""#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_dxf() {
+        let mut buffer = Vec::new();
+        write_dxf(&mut buffer, &[layer()]).unwrap();
+        let dxf = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = dxf.lines().collect();
+
+        assert_eq!(&lines[..4], ["0", "SECTION", "2", "HEADER"]);
+        assert!(dxf.contains("$ACADVER\n1\nAC1015\n"));
+        assert_eq!(dxf.matches("\nLWPOLYLINE\n").count(), 2);
+        assert!(!dxf.contains("\nVERTEX\n"));
+        assert!(dxf.contains("\n8\nSLICE_1\n100\nAcDbPolyline\n90\n4\n70\n1\n38\n0.5\n"));
+        assert!(dxf.contains("\n2\nSLICE_1\n"));
+        assert_eq!(&lines[lines.len() - 2..], ["0", "EOF"]);
+
+        // every group code is followed by a value and handles are unique
+        assert_eq!(lines.len() % 2, 0);
+        let mut handles: Vec<&str> = lines
+            .chunks(2)
+            .filter(|pair| pair[0] == "5" || pair[0] == "105")
+            .map(|pair| pair[1])
+            .collect();
+        let seed = lines
+            .chunks(2)
+            .skip_while(|pair| pair[1] != "$HANDSEED")
+            .nth(1)
+            .unwrap()[1];
+        handles.retain(|&h| h != seed);
+        let count = handles.len();
+        handles.sort_unstable();
+        handles.dedup();
+        assert_eq!(handles.len(), count);
+        assert_eq!(u32::from_str_radix(seed, 16).unwrap() as usize, count + 1);
+    }
+}
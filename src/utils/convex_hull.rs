@@ -0,0 +1,359 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use anyhow::anyhow;
+
+use super::{
+    mesh::IndexedMesh,
+    point::{cross, dot, sub, to_f64, to_vec3, Point},
+    stl_parser::{Triangle, Triangles},
+};
+
+/// Coarsest tolerance tried after rounding errors, relative to the first one.
+const MAX_EPS_FACTOR: f64 = 1e4;
+
+/// Convex hull of all vertices as a closed, outward oriented mesh.
+///
+/// Quickhull in `f64` with a tolerance relative to the extent of the points;
+/// points within the tolerance of a hull facet count as inside, so coplanar
+/// vertices do not end up on the hull. When rounding errors leave the hull
+/// inconsistent, it is rebuilt with a ten times coarser tolerance, which
+/// absorbs the nearly coplanar points that caused the trouble. Fails when the
+/// vertices do not span a volume, or when even the coarsest tolerance does
+/// not help.
+pub fn convex_hull(triangles: &Triangles) -> anyhow::Result<Triangles> {
+    let points: Vec<Point> = IndexedMesh::from_triangles(triangles)
+        .vertices
+        .into_iter()
+        .map(to_f64)
+        .collect();
+
+    let mut factor = 1.0;
+    while factor <= MAX_EPS_FACTOR {
+        let Some(mut hull) = Hull::new(&points, factor) else {
+            if factor == 1.0 {
+                return Err(anyhow!("The vertices do not span a volume!"));
+            }
+            break;
+        };
+        if hull.expand().is_some() {
+            return Ok(hull
+                .faces
+                .iter()
+                .filter(|face| face.alive)
+                .map(|face| Triangle {
+                    normal: to_vec3(face.normal),
+                    vertices: face.vertices.map(|v| to_vec3(points[v])),
+                })
+                .collect());
+        }
+        factor *= 10.0;
+    }
+    Err(anyhow!("Rounding errors left the hull inconsistent!"))
+}
+
+#[derive(Debug, Clone)]
+struct Face {
+    vertices: [usize; 3],
+    normal: Point,
+    offset: f64,
+    /// Points in front of this face that are not yet on the hull.
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+struct Hull<'a> {
+    points: &'a [Point],
+    eps: f64,
+    faces: Vec<Face>,
+    /// Directed edge `(a, b)` to the face that contains it.
+    edges: HashMap<(usize, usize), usize>,
+    /// Faces that were given outside points, possibly dead by now, oldest
+    /// first.
+    pending: BinaryHeap<Reverse<usize>>,
+}
+
+impl<'a> Hull<'a> {
+    /// Builds the initial tetrahedron from extreme points, with the tolerance
+    /// scaled by `eps_factor`.
+    fn new(points: &'a [Point], eps_factor: f64) -> Option<Self> {
+        let scale = (0..3)
+            .map(|i| points.iter().map(|p| p[i].abs()).fold(0.0, f64::max))
+            .sum::<f64>();
+        let eps = eps_factor * 3.0 * f32::EPSILON as f64 * scale.max(f64::MIN_POSITIVE);
+
+        let extremes: Vec<usize> = (0..3)
+            .flat_map(|i| {
+                let by_axis = |&a: &usize, &b: &usize| points[a][i].total_cmp(&points[b][i]);
+                let indices = 0..points.len();
+                [indices.clone().min_by(by_axis), indices.max_by(by_axis)]
+            })
+            .flatten()
+            .collect();
+
+        let distance2 = |a: usize, b: usize| {
+            let d = sub(points[a], points[b]);
+            dot(d, d)
+        };
+        let (a, b) = extremes
+            .iter()
+            .flat_map(|&a| extremes.iter().map(move |&b| (a, b)))
+            .max_by(|&(a, b), &(c, d)| distance2(a, b).total_cmp(&distance2(c, d)))?;
+        if distance2(a, b).sqrt() <= eps {
+            return None;
+        }
+
+        let line = sub(points[b], points[a]);
+        let from_line = |p: usize| {
+            let c = cross(line, sub(points[p], points[a]));
+            dot(c, c)
+        };
+        let c = (0..points.len()).max_by(|&p, &q| from_line(p).total_cmp(&from_line(q)))?;
+        if from_line(c).sqrt() / dot(line, line).sqrt() <= eps {
+            return None;
+        }
+
+        let normal = cross(line, sub(points[c], points[a]));
+        let from_plane =
+            |p: usize| dot(normal, sub(points[p], points[a])) / dot(normal, normal).sqrt();
+        let d = (0..points.len())
+            .max_by(|&p, &q| from_plane(p).abs().total_cmp(&from_plane(q).abs()))?;
+        if from_plane(d).abs() <= eps {
+            return None;
+        }
+
+        let mut hull = Hull {
+            points,
+            eps,
+            faces: Vec::new(),
+            edges: HashMap::new(),
+            pending: BinaryHeap::new(),
+        };
+        // wind the base so that the fourth point lies behind it
+        let (b, c) = if from_plane(d) > 0.0 { (c, b) } else { (b, c) };
+        for vertices in [[a, b, c], [a, d, b], [b, d, c], [c, d, a]] {
+            hull.add_face(vertices);
+        }
+
+        let first: Vec<usize> = (0..hull.faces.len()).collect();
+        hull.assign(
+            (0..points.len()).filter(|p| ![a, b, c, d].contains(p)),
+            &first,
+        );
+        Some(hull)
+    }
+
+    fn add_face(&mut self, vertices: [usize; 3]) -> usize {
+        let [a, b, c] = vertices.map(|v| self.points[v]);
+        let n = cross(sub(b, a), sub(c, a));
+        let length = dot(n, n).sqrt();
+        let normal = if length > 0.0 {
+            n.map(|x| x / length)
+        } else {
+            [0.0; 3]
+        };
+
+        let index = self.faces.len();
+        for i in 0..3 {
+            self.edges
+                .insert((vertices[i], vertices[(i + 1) % 3]), index);
+        }
+        self.faces.push(Face {
+            vertices,
+            normal,
+            offset: dot(normal, a),
+            outside: Vec::new(),
+            alive: true,
+        });
+        index
+    }
+
+    fn distance(&self, face: usize, p: usize) -> f64 {
+        dot(self.faces[face].normal, self.points[p]) - self.faces[face].offset
+    }
+
+    /// Gives each point to the first face it lies in front of.
+    fn assign(&mut self, points: impl IntoIterator<Item = usize>, faces: &[usize]) {
+        for p in points {
+            if let Some(&face) = faces.iter().find(|&&f| self.distance(f, p) > self.eps) {
+                if self.faces[face].outside.is_empty() {
+                    self.pending.push(Reverse(face));
+                }
+                self.faces[face].outside.push(p);
+            }
+        }
+    }
+
+    /// Adds the farthest outside point of a pending face until no face has
+    /// outside points left. Returns `None` if rounding broke the topology,
+    /// leaving a horizon edge without a face on its other side.
+    fn expand(&mut self) -> Option<()> {
+        while let Some(Reverse(start)) = self.pending.pop() {
+            if !self.faces[start].alive || self.faces[start].outside.is_empty() {
+                continue;
+            }
+            let eye = *self.faces[start]
+                .outside
+                .iter()
+                .max_by(|&&p, &&q| self.distance(start, p).total_cmp(&self.distance(start, q)))
+                .unwrap();
+
+            // faces that see the eye point form a connected patch around `start`
+            let mut visible = vec![start];
+            let mut is_visible: HashMap<usize, bool> = HashMap::from([(start, true)]);
+            let mut horizon = Vec::new();
+            let mut i = 0;
+            while i < visible.len() {
+                let face = visible[i];
+                i += 1;
+                let vertices = self.faces[face].vertices;
+                for k in 0..3 {
+                    let (a, b) = (vertices[k], vertices[(k + 1) % 3]);
+                    let neighbour = *self.edges.get(&(b, a))?;
+                    if !self.faces[neighbour].alive {
+                        return None;
+                    }
+                    match is_visible.get(&neighbour) {
+                        Some(true) => {}
+                        Some(false) => horizon.push((a, b)),
+                        None => {
+                            let seen = self.distance(neighbour, eye) > self.eps;
+                            is_visible.insert(neighbour, seen);
+                            if seen {
+                                visible.push(neighbour);
+                            } else {
+                                horizon.push((a, b));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut orphans = Vec::new();
+            for &face in &visible {
+                let face = &mut self.faces[face];
+                face.alive = false;
+                orphans.append(&mut face.outside);
+                for k in 0..3 {
+                    self.edges
+                        .remove(&(face.vertices[k], face.vertices[(k + 1) % 3]));
+                }
+            }
+
+            let new_faces: Vec<usize> = horizon
+                .into_iter()
+                .map(|(a, b)| self.add_face([a, b, eye]))
+                .collect();
+            self.assign(orphans.into_iter().filter(|&p| p != eye), &new_faces);
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{mass_properties::MassProperties, mesh_report::MeshReport, vec3::Vec3};
+
+    fn points(points: &[(f32, f32, f32)]) -> Triangles {
+        points
+            .chunks(3)
+            .map(|chunk| {
+                let p = |i: usize| Vec3::from(chunk[i.min(chunk.len() - 1)]);
+                Triangle::from_vertices([p(0), p(1), p(2)])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cube_with_inner_points() {
+        let mut input = Vec::new();
+        for i in 0..8 {
+            input.push(((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32));
+        }
+        // inside, on faces and on edges
+        input.extend([
+            (0.5, 0.5, 0.5),
+            (0.5, 0.5, 1.0),
+            (0.0, 0.3, 0.2),
+            (1.0, 0.5, 0.0),
+        ]);
+        input.extend([(0.2, 0.7, 0.4), (0.9, 0.1, 0.3)]);
+
+        let hull = convex_hull(&points(&input)).unwrap();
+        assert_eq!(hull.len(), 12);
+        let report = MeshReport::new(&hull);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert!((MassProperties::new(&hull, 1.0).volume - 1.0).abs() < 1e-6);
+        for triangle in &hull {
+            assert!((triangle.normal - triangle.unit_normal()).d3() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_points_on_a_sphere() {
+        let mut input = Vec::new();
+        for i in 0..20 {
+            for j in 0..10 {
+                let (theta, phi) = (i as f32 * 0.314_159_26, (j as f32 + 0.5) * 0.314_159_26);
+                input.push((phi.sin() * theta.cos(), phi.sin() * theta.sin(), phi.cos()));
+            }
+        }
+        let hull = convex_hull(&points(&input)).unwrap();
+        let report = MeshReport::new(&hull);
+        assert_eq!(report.vertices, 200);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!(report.genus, Some(0));
+
+        // every input point lies behind every hull facet
+        for triangle in &hull {
+            for p in &input {
+                let p = Vec3::from(*p);
+                assert!(triangle.normal.dot(p - triangle.vertices[0]) < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_input() {
+        let input = [
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0),
+            (1.0, 1.0, 0.0),
+        ];
+        let error = convex_hull(&points(&input)).unwrap_err();
+        assert_eq!(error.to_string(), "The vertices do not span a volume!");
+    }
+
+    #[test]
+    fn test_many_nearly_coplanar_points() {
+        // grids on the faces of a cube, jittered around the f32 resolution
+        let mut seed = 17u32;
+        let mut jitter = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            (seed as f32 / u32::MAX as f32 - 0.5) * 1e-6
+        };
+        let mut input = Vec::new();
+        for axis in 0..3 {
+            for side in [0.0, 1.0] {
+                for i in 0..=40 {
+                    for j in 0..=40 {
+                        let mut p = [i as f32 / 40.0, j as f32 / 40.0, side];
+                        p.rotate_right(axis);
+                        input.push((p[0] + jitter(), p[1] + jitter(), p[2] + jitter()));
+                    }
+                }
+            }
+        }
+
+        let hull = convex_hull(&points(&input)).unwrap();
+        let report = MeshReport::new(&hull);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert!((MassProperties::new(&hull, 1.0).volume - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::HashMap;
+
+use super::{
+    bounding_box::Aabb,
+    point::{cross, dot, lerp, sub, to_f64, to_vec3, Point},
+    stl_parser::{Triangle, Triangles},
+};
+
+/// Distance below which a point counts as lying on a splitting plane, relative
+/// to the diagonal of the bounding box of both meshes.
+const PLANE_EPS: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BooleanOp {
+    /// everything inside either mesh
+    Union,
+    /// the first mesh with the second one cut away
+    Difference,
+    /// everything inside both meshes
+    Intersection,
+}
+
+/// Combines two closed, outward oriented meshes.
+///
+/// Both meshes are put into BSP trees that clip each other, following the
+/// approach of csg.js. Splitting along the planes of the other mesh leaves
+/// T-junctions and tiny slivers; vertices are snapped together and the
+/// T-junctions removed before the polygons are triangulated, so closed inputs
+/// give a closed result. Nearly coplanar but noisy surfaces can still leave
+/// cracks below the tolerance, a millionth of the size of both meshes.
+pub fn boolean(a: &Triangles, b: &Triangles, op: BooleanOp) -> Triangles {
+    let bounds = Aabb::from_triangles(a).union(&Aabb::from_triangles(b));
+    let eps = if bounds.is_empty() {
+        PLANE_EPS
+    } else {
+        PLANE_EPS * (bounds.size().d3() as f64).max(f64::MIN_POSITIVE)
+    };
+    let mut a = Bsp::new(polygons(a), eps);
+    let mut b = Bsp::new(polygons(b), eps);
+
+    match op {
+        BooleanOp::Union => {
+            a.clip_to(&b);
+            b.clip_to(&a);
+            b.invert();
+            b.clip_to(&a);
+            b.invert();
+            a.build(b.all_polygons());
+        }
+        BooleanOp::Difference => {
+            a.invert();
+            a.clip_to(&b);
+            b.clip_to(&a);
+            b.invert();
+            b.clip_to(&a);
+            b.invert();
+            a.build(b.all_polygons());
+            a.invert();
+        }
+        BooleanOp::Intersection => {
+            a.invert();
+            b.clip_to(&a);
+            b.invert();
+            a.clip_to(&b);
+            b.clip_to(&a);
+            a.build(b.all_polygons());
+            a.invert();
+        }
+    }
+
+    let mut result = a.all_polygons();
+    snap_vertices(&mut result, eps);
+    remove_t_junctions(&mut result, eps);
+    result
+        .iter()
+        .flat_map(|polygon| polygon.triangulate(eps))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Point,
+    w: f64,
+}
+
+impl Plane {
+    fn from_points(a: Point, b: Point, c: Point) -> Option<Plane> {
+        let n = cross(sub(b, a), sub(c, a));
+        let length = dot(n, n).sqrt();
+        if length <= f64::EPSILON {
+            return None;
+        }
+        let normal = n.map(|x| x / length);
+        Some(Plane {
+            normal,
+            w: dot(normal, a),
+        })
+    }
+
+    fn flip(&mut self) {
+        self.normal = self.normal.map(|x| -x);
+        self.w = -self.w;
+    }
+
+    fn distance(&self, p: Point) -> f64 {
+        dot(self.normal, p) - self.w
+    }
+
+    /// Sorts `polygon` into the given lists, splitting it when it crosses the
+    /// plane by more than `eps`.
+    fn split(
+        &self,
+        polygon: Polygon,
+        eps: f64,
+        coplanar_front: &mut Vec<Polygon>,
+        coplanar_back: &mut Vec<Polygon>,
+        front: &mut Vec<Polygon>,
+        back: &mut Vec<Polygon>,
+    ) {
+        const COPLANAR: u8 = 0;
+        const FRONT: u8 = 1;
+        const BACK: u8 = 2;
+        const SPANNING: u8 = 3;
+
+        let types: Vec<u8> = polygon
+            .vertices
+            .iter()
+            .map(|&p| {
+                let t = self.distance(p);
+                if t < -eps {
+                    BACK
+                } else if t > eps {
+                    FRONT
+                } else {
+                    COPLANAR
+                }
+            })
+            .collect();
+
+        match types.iter().fold(COPLANAR, |all, t| all | t) {
+            COPLANAR => {
+                if dot(self.normal, polygon.plane.normal) > 0.0 {
+                    coplanar_front.push(polygon);
+                } else {
+                    coplanar_back.push(polygon);
+                }
+            }
+            FRONT => front.push(polygon),
+            BACK => back.push(polygon),
+            _ => {
+                let n = polygon.vertices.len();
+                let (mut f, mut b) = (Vec::new(), Vec::new());
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    let (ti, tj) = (types[i], types[j]);
+                    let (vi, vj) = (polygon.vertices[i], polygon.vertices[j]);
+                    if ti != BACK {
+                        f.push(vi);
+                    }
+                    if ti != FRONT {
+                        b.push(vi);
+                    }
+                    if ti | tj == SPANNING {
+                        // interpolate in a fixed direction so that both facets
+                        // sharing the edge get exactly the same point
+                        let (p, q) = if vi < vj { (vi, vj) } else { (vj, vi) };
+                        let t = (self.w - dot(self.normal, p)) / dot(self.normal, sub(q, p));
+                        let v = lerp(p, q, t);
+                        f.push(v);
+                        b.push(v);
+                    }
+                }
+                if f.len() >= 3 {
+                    front.push(Polygon {
+                        vertices: f,
+                        plane: polygon.plane,
+                    });
+                }
+                if b.len() >= 3 {
+                    back.push(Polygon {
+                        vertices: b,
+                        plane: polygon.plane,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Convex planar polygon.
+#[derive(Debug, Clone)]
+struct Polygon {
+    vertices: Vec<Point>,
+    plane: Plane,
+}
+
+impl Polygon {
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        self.plane.flip();
+    }
+
+    /// Fans the polygon around its first corner, or around its centroid when
+    /// vertices were inserted along the edges, which would make the simple fan
+    /// degenerate.
+    fn triangulate(&self, eps: f64) -> Triangles {
+        let n = self.vertices.len();
+        let normal = to_vec3(self.plane.normal);
+        let triangle = |a: Point, b: Point, c: Point| Triangle {
+            normal,
+            vertices: [a, b, c].map(to_vec3),
+        };
+
+        let collinear = (0..n).any(|i| {
+            let (prev, v, next) = (
+                self.vertices[(i + n - 1) % n],
+                self.vertices[i],
+                self.vertices[(i + 1) % n],
+            );
+            let (u, w) = (sub(prev, v), sub(next, v));
+            let area = cross(u, w);
+            dot(area, area).sqrt() <= eps * dot(u, u).sqrt().max(dot(w, w).sqrt())
+        });
+
+        if collinear {
+            let centroid = self
+                .vertices
+                .iter()
+                .fold([0.0; 3], |sum, p| std::array::from_fn(|i| sum[i] + p[i]))
+                .map(|x| x / n as f64);
+            (0..n)
+                .map(|i| triangle(centroid, self.vertices[i], self.vertices[(i + 1) % n]))
+                .collect()
+        } else {
+            (1..n - 1)
+                .map(|i| triangle(self.vertices[0], self.vertices[i], self.vertices[i + 1]))
+                .collect()
+        }
+    }
+}
+
+fn polygons(triangles: &Triangles) -> Vec<Polygon> {
+    triangles
+        .iter()
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.vertices.map(to_f64);
+            Plane::from_points(a, b, c).map(|plane| Polygon {
+                vertices: vec![a, b, c],
+                plane,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    plane: Option<Plane>,
+    front: Option<usize>,
+    back: Option<usize>,
+    polygons: Vec<Polygon>,
+}
+
+/// BSP tree stored in an arena so that deep trees do not recurse.
+#[derive(Debug)]
+struct Bsp {
+    nodes: Vec<Node>,
+    /// Tolerance of the plane tests.
+    eps: f64,
+}
+
+impl Bsp {
+    fn new(polygons: Vec<Polygon>, eps: f64) -> Self {
+        let mut bsp = Bsp {
+            nodes: vec![Node::default()],
+            eps,
+        };
+        bsp.build(polygons);
+        bsp
+    }
+
+    fn build(&mut self, polygons: Vec<Polygon>) {
+        let mut stack = vec![(0, polygons)];
+        while let Some((node, polygons)) = stack.pop() {
+            if polygons.is_empty() {
+                continue;
+            }
+            let plane = *self.nodes[node].plane.get_or_insert(polygons[0].plane);
+
+            let (mut coplanar, mut front, mut back) = (Vec::new(), Vec::new(), Vec::new());
+            for polygon in polygons {
+                let mut coplanar_back = Vec::new();
+                plane.split(
+                    polygon,
+                    self.eps,
+                    &mut coplanar,
+                    &mut coplanar_back,
+                    &mut front,
+                    &mut back,
+                );
+                coplanar.append(&mut coplanar_back);
+            }
+            self.nodes[node].polygons.append(&mut coplanar);
+
+            if !front.is_empty() {
+                let child = self.child(node, true);
+                stack.push((child, front));
+            }
+            if !back.is_empty() {
+                let child = self.child(node, false);
+                stack.push((child, back));
+            }
+        }
+    }
+
+    fn child(&mut self, node: usize, front: bool) -> usize {
+        let next = self.nodes.len();
+        let slot = if front {
+            &mut self.nodes[node].front
+        } else {
+            &mut self.nodes[node].back
+        };
+        let child = *slot.get_or_insert(next);
+        if child == next {
+            self.nodes.push(Node::default());
+        }
+        child
+    }
+
+    /// Turns solid space into empty space and back.
+    fn invert(&mut self) {
+        for node in &mut self.nodes {
+            node.polygons.iter_mut().for_each(Polygon::flip);
+            if let Some(plane) = &mut node.plane {
+                plane.flip();
+            }
+            std::mem::swap(&mut node.front, &mut node.back);
+        }
+    }
+
+    /// Removes the parts of `polygons` that lie inside the solid of this tree.
+    fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        let mut result = Vec::new();
+        let mut stack = vec![(0, polygons)];
+        while let Some((node, polygons)) = stack.pop() {
+            let node = &self.nodes[node];
+            let Some(plane) = node.plane else {
+                result.extend(polygons);
+                continue;
+            };
+
+            let (mut front, mut back) = (Vec::new(), Vec::new());
+            for polygon in polygons {
+                let (mut coplanar_front, mut coplanar_back) = (Vec::new(), Vec::new());
+                plane.split(
+                    polygon,
+                    self.eps,
+                    &mut coplanar_front,
+                    &mut coplanar_back,
+                    &mut front,
+                    &mut back,
+                );
+                front.append(&mut coplanar_front);
+                back.append(&mut coplanar_back);
+            }
+
+            match node.front {
+                Some(child) => stack.push((child, front)),
+                None => result.extend(front),
+            }
+            if let Some(child) = node.back {
+                stack.push((child, back));
+            }
+        }
+        result
+    }
+
+    /// Removes the parts of this tree's polygons that lie inside `other`.
+    fn clip_to(&mut self, other: &Bsp) {
+        for node in &mut self.nodes {
+            node.polygons = other.clip_polygons(std::mem::take(&mut node.polygons));
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.polygons.iter().cloned())
+            .collect()
+    }
+}
+
+/// Merges vertices closer than `eps`, which repeated splitting leaves behind,
+/// and drops the polygons that collapse in the process.
+fn snap_vertices(polygons: &mut Vec<Polygon>, eps: f64) {
+    let cell = |p: Point| p.map(|x| (x / eps).floor() as i64);
+    let mut grid: HashMap<[i64; 3], Vec<Point>> = HashMap::new();
+    let mut snap = |p: Point| {
+        let [x, y, z] = cell(p);
+        for key in (-1..=1)
+            .flat_map(|i| (-1..=1).flat_map(move |j| (-1..=1).map(move |k| [x + i, y + j, z + k])))
+        {
+            for &q in grid.get(&key).into_iter().flatten() {
+                let d = sub(p, q);
+                if dot(d, d) <= eps * eps {
+                    return q;
+                }
+            }
+        }
+        grid.entry([x, y, z]).or_default().push(p);
+        p
+    };
+
+    for polygon in polygons.iter_mut() {
+        polygon.vertices = polygon.vertices.iter().map(|&p| snap(p)).collect();
+        remove_spikes(&mut polygon.vertices);
+    }
+    // slivers whose corners all lie on the line of their longest edge are
+    // dropped, the T-junction pass then stitches their neighbours together
+    polygons.retain(|polygon| {
+        let n = polygon.vertices.len();
+        let Some((a, b)) = (0..n)
+            .map(|i| (polygon.vertices[i], polygon.vertices[(i + 1) % n]))
+            .max_by(|&(a, b), &(c, d)| {
+                dot(sub(b, a), sub(b, a)).total_cmp(&dot(sub(d, c), sub(d, c)))
+            })
+        else {
+            return false;
+        };
+        n >= 3
+            && polygon
+                .vertices
+                .iter()
+                .any(|&p| distance_to_line(p, a, b).1 > eps)
+    });
+}
+
+/// Parameter of the projection of `p` onto the line through `a` and `b`, and
+/// the distance of `p` from that line.
+fn distance_to_line(p: Point, a: Point, b: Point) -> (f64, f64) {
+    let ab = sub(b, a);
+    let t = dot(sub(p, a), ab) / dot(ab, ab);
+    let off = sub(p, lerp(a, b, t));
+    (t, dot(off, off).sqrt())
+}
+
+/// Removes repeated vertices and the back and forth spikes `a, b, a` that
+/// appear when snapping collapses a thin polygon.
+fn remove_spikes(vertices: &mut Vec<Point>) {
+    let mut i = 0;
+    while vertices.len() >= 3 && i < vertices.len() {
+        let n = vertices.len();
+        let (next, after) = ((i + 1) % n, (i + 2) % n);
+        if vertices[i] == vertices[next] {
+            vertices.remove(next);
+        } else if vertices[i] == vertices[after] {
+            // drop the tip and one of the two copies
+            vertices.remove(next.max(after));
+            vertices.remove(next.min(after));
+        } else {
+            i += 1;
+            continue;
+        }
+        i = 0;
+    }
+}
+
+/// Inserts every vertex that lies on an edge of another polygon into that edge.
+fn remove_t_junctions(polygons: &mut [Polygon], eps: f64) {
+    let mut points: Vec<Point> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.vertices.iter().copied())
+        .collect();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    points.dedup();
+
+    for polygon in polygons.iter_mut() {
+        let n = polygon.vertices.len();
+        let mut vertices = Vec::with_capacity(n);
+        for i in 0..n {
+            let (a, b) = (polygon.vertices[i], polygon.vertices[(i + 1) % n]);
+            vertices.push(a);
+
+            let start = points.partition_point(|p| p[0] < a[0].min(b[0]) - eps);
+            let end = points.partition_point(|p| p[0] <= a[0].max(b[0]) + eps);
+
+            let mut on_edge: Vec<(f64, Point)> = points[start..end]
+                .iter()
+                .filter(|&&p| p != a && p != b)
+                .filter_map(|&p| {
+                    let (t, distance) = distance_to_line(p, a, b);
+                    (t > 0.0 && t < 1.0 && distance <= eps).then_some((t, p))
+                })
+                .collect();
+            on_edge.sort_by(|x, y| x.0.total_cmp(&y.0));
+            vertices.extend(on_edge.into_iter().map(|(_, p)| p));
+        }
+        remove_spikes(&mut vertices);
+        polygon.vertices = vertices;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        fixtures::cuboid, mass_properties::MassProperties, mesh_report::MeshReport,
+    };
+
+    fn check(triangles: &Triangles, volume: f64) {
+        let report = MeshReport::new(triangles);
+        assert!(report.is_watertight(), "{}", report);
+        assert!(report.is_manifold(), "{}", report);
+        let actual = MassProperties::new(triangles, 1.0).volume;
+        assert!((actual - volume).abs() < 1e-5, "{} != {}", actual, volume);
+    }
+
+    #[test]
+    fn test_overlapping_boxes() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let b = cuboid((0.5, 0.5, 0.5).into(), (1.5, 1.5, 1.5).into());
+
+        check(&boolean(&a, &b, BooleanOp::Union), 1.875);
+        check(&boolean(&a, &b, BooleanOp::Difference), 0.875);
+        check(&boolean(&a, &b, BooleanOp::Intersection), 0.125);
+    }
+
+    #[test]
+    fn test_inner_cavity() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let b = cuboid((0.25, 0.25, 0.25).into(), (0.75, 0.75, 0.75).into());
+
+        let hollow = boolean(&a, &b, BooleanOp::Difference);
+        check(&hollow, 0.875);
+        assert_eq!(MeshReport::new(&hollow).components, 2);
+        check(&boolean(&a, &b, BooleanOp::Union), 1.0);
+        check(&boolean(&b, &a, BooleanOp::Difference), 0.0);
+    }
+
+    #[test]
+    fn test_flush_faces() {
+        // the boxes share the face x = 1 and part of the faces y = 0 and z = 0
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let b = cuboid((1.0, 0.0, 0.0).into(), (2.0, 0.5, 0.5).into());
+        check(&boolean(&a, &b, BooleanOp::Union), 1.25);
+        check(&boolean(&a, &b, BooleanOp::Difference), 1.0);
+    }
+
+    #[test]
+    fn test_tolerance_follows_the_size() {
+        // overlapping boxes far smaller and far larger than the unit
+        for scale in [1e-5, 1e4] {
+            let p = |x: f32, y: f32, z: f32| (x * scale, y * scale, z * scale).into();
+            let a = cuboid(p(0.0, 0.0, 0.0), p(1.0, 1.0, 1.0));
+            let b = cuboid(p(0.5, 0.5, 0.5), p(1.3, 1.3, 1.3));
+            let volume = |v: f64| v * (scale as f64).powi(3);
+            let union = boolean(&a, &b, BooleanOp::Union);
+            let report = MeshReport::new(&union);
+            assert!(report.is_watertight() && report.is_manifold());
+            let relative = MassProperties::new(&union, 1.0).volume / volume(1.0 + 0.512 - 0.125);
+            assert!(
+                (relative - 1.0).abs() < 1e-4,
+                "scale {}: {}",
+                scale,
+                relative
+            );
+        }
+    }
+}
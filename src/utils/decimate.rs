@@ -0,0 +1,442 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+};
+
+use super::{
+    mesh::IndexedMesh,
+    point::{cross, dot, sub, to_f64, Point},
+    stl_parser::{Triangle, Triangles},
+    vec3::Vec3,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DecimateOptions {
+    /// Stop once the mesh has at most this many facets.
+    pub target_facets: Option<usize>,
+    /// Never perform a collapse whose quadric error (sum of squared distances
+    /// to the original planes) exceeds this value.
+    pub max_error: Option<f64>,
+}
+
+/// Simplifies the mesh by quadric error metric edge collapses (Garland & Heckbert).
+///
+/// Boundary and non-manifold vertices never move, and collapses that would
+/// flip a facet, create a degenerate facet or break the manifold topology are
+/// skipped. Returns the number of removed facets.
+pub fn decimate(triangles: &mut Triangles, options: &DecimateOptions) -> usize {
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let before = triangles.len();
+    let mut decimator = Decimator::new(&mesh);
+    let target = options.target_facets.unwrap_or(0);
+    let max_error = options.max_error.unwrap_or(f64::INFINITY);
+
+    while decimator.live_faces > target {
+        let Some(candidate) = decimator.heap.pop() else {
+            break;
+        };
+        if candidate.cost > max_error {
+            break;
+        }
+        decimator.try_collapse(candidate);
+    }
+
+    *triangles = decimator.into_triangles();
+    before - triangles.len()
+}
+
+/// Symmetric 4x4 quadric stored as its upper triangle.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(n: Point, d: f64) -> Self {
+        let [a, b, c] = n;
+        Quadric([
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ])
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        Quadric(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+
+    fn error(&self, p: Point) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = p;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// Point minimizing the error, `None` if the system is ill-conditioned.
+    fn minimizer(&self) -> Option<Point> {
+        let q = &self.0;
+        let a = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let b = [-q[3], -q[6], -q[8]];
+        let det = det3(a);
+        let scale = a.iter().flatten().fold(0.0f64, |m, v| m.max(v.abs()));
+        if det.abs() <= 1e-10 * scale.powi(3) || scale == 0.0 {
+            return None;
+        }
+        Some(std::array::from_fn(|k| {
+            let mut ak = a;
+            for (row, value) in ak.iter_mut().zip(b) {
+                row[k] = value;
+            }
+            det3(ak) / det
+        }))
+    }
+}
+
+fn det3(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    cost: f64,
+    edge: (usize, usize),
+    versions: (u32, u32),
+    position: Point,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // reversed so that the max-heap pops the cheapest collapse
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Decimator {
+    positions: Vec<Point>,
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    vertex_faces: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    locked: Vec<bool>,
+    versions: Vec<u32>,
+    heap: BinaryHeap<Candidate>,
+    live_faces: usize,
+}
+
+impl Decimator {
+    fn new(mesh: &IndexedMesh) -> Self {
+        let positions: Vec<Point> = mesh.vertices.iter().copied().map(to_f64).collect();
+        let face_alive: Vec<bool> = mesh
+            .faces
+            .iter()
+            .map(|face| !IndexedMesh::is_degenerate(face))
+            .collect();
+
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        for (face, _) in mesh
+            .faces
+            .iter()
+            .zip(&face_alive)
+            .filter(|(_, &alive)| alive)
+        {
+            let [a, b, c] = face.map(|v| positions[v]);
+            let n = cross(sub(b, a), sub(c, a));
+            let length = dot(n, n).sqrt();
+            if length == 0.0 {
+                continue;
+            }
+            let n = n.map(|x| x / length);
+            let plane = Quadric::from_plane(n, -dot(n, a));
+            for &v in face {
+                quadrics[v] = quadrics[v].add(&plane);
+            }
+        }
+
+        let mut locked = vec![false; positions.len()];
+        for ((a, b), faces) in mesh.edge_faces() {
+            if faces.len() != 2 {
+                locked[a] = true;
+                locked[b] = true;
+            }
+        }
+
+        let mut decimator = Decimator {
+            positions,
+            faces: mesh.faces.clone(),
+            live_faces: face_alive.iter().filter(|&&alive| alive).count(),
+            face_alive,
+            vertex_faces: mesh.vertex_faces(),
+            quadrics,
+            locked,
+            versions: vec![0; mesh.vertices.len()],
+            heap: BinaryHeap::new(),
+        };
+
+        for (a, b) in mesh.edge_faces().into_keys() {
+            decimator.push_edge(a, b);
+        }
+        decimator
+    }
+
+    fn push_edge(&mut self, a: usize, b: usize) {
+        let (a, b) = match (self.locked[a], self.locked[b]) {
+            (true, true) => return,
+            // collapse onto the locked vertex
+            (false, true) => (b, a),
+            _ => (a, b),
+        };
+
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let position = if self.locked[a] {
+            pa
+        } else {
+            let midpoint = std::array::from_fn(|i| (pa[i] + pb[i]) / 2.0);
+            let mut options = vec![pa, pb, midpoint];
+            options.extend(quadric.minimizer());
+            options
+                .into_iter()
+                .min_by(|&p, &q| quadric.error(p).total_cmp(&quadric.error(q)))
+                .unwrap()
+        };
+
+        self.heap.push(Candidate {
+            cost: quadric.error(position).max(0.0),
+            edge: (a, b),
+            versions: (self.versions[a], self.versions[b]),
+            position,
+        });
+    }
+
+    fn live_faces_of(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_faces[v]
+            .iter()
+            .copied()
+            .filter(|&f| self.face_alive[f])
+    }
+
+    fn neighbours(&self, v: usize) -> HashSet<usize> {
+        self.live_faces_of(v)
+            .flat_map(|f| self.faces[f])
+            .filter(|&w| w != v)
+            .collect()
+    }
+
+    /// Collapses `b` into `a` if the candidate is still current and the collapse is safe.
+    fn try_collapse(&mut self, candidate: Candidate) {
+        let (a, b) = candidate.edge;
+        if candidate.versions != (self.versions[a], self.versions[b]) {
+            return;
+        }
+
+        let shared: Vec<usize> = self
+            .live_faces_of(a)
+            .filter(|&f| self.faces[f].contains(&b))
+            .collect();
+        if shared.is_empty() {
+            return;
+        }
+
+        // link condition: the only common neighbours are the tips of the shared faces
+        let common = self.neighbours(a).intersection(&self.neighbours(b)).count();
+        if common != shared.len() {
+            return;
+        }
+
+        let moved: Vec<usize> = self
+            .live_faces_of(a)
+            .chain(self.live_faces_of(b))
+            .filter(|f| !shared.contains(f))
+            .collect();
+        for &f in &moved {
+            let before = self.faces[f].map(|v| self.positions[v]);
+            let after = self.faces[f].map(|v| {
+                if v == a || v == b {
+                    candidate.position
+                } else {
+                    self.positions[v]
+                }
+            });
+            let n0 = cross(sub(before[1], before[0]), sub(before[2], before[0]));
+            let n1 = cross(sub(after[1], after[0]), sub(after[2], after[0]));
+            let (l0, l1) = (dot(n0, n0).sqrt(), dot(n1, n1).sqrt());
+            if l1 <= 1e-12 * l0 || dot(n0, n1) <= 0.0 {
+                return;
+            }
+        }
+
+        for &f in &shared {
+            self.face_alive[f] = false;
+            self.live_faces -= 1;
+        }
+        let b_faces = std::mem::take(&mut self.vertex_faces[b]);
+        for f in b_faces {
+            if self.face_alive[f] {
+                for v in self.faces[f].iter_mut() {
+                    if *v == b {
+                        *v = a;
+                    }
+                }
+                self.vertex_faces[a].push(f);
+            }
+        }
+        self.vertex_faces[a].retain(|&f| self.face_alive[f]);
+
+        self.positions[a] = candidate.position;
+        self.quadrics[a] = self.quadrics[a].add(&self.quadrics[b]);
+        self.versions[a] += 1;
+        self.versions[b] += 1;
+
+        for w in self.neighbours(a) {
+            self.push_edge(a, w);
+        }
+    }
+
+    fn into_triangles(self) -> Triangles {
+        self.faces
+            .iter()
+            .zip(&self.face_alive)
+            .filter(|(_, &alive)| alive)
+            .map(|(face, _)| {
+                Triangle::from_vertices(face.map(|v| {
+                    let p = self.positions[v];
+                    Vec3::from((p[0] as f32, p[1] as f32, p[2] as f32))
+                }))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{mass_properties::MassProperties, mesh_report::MeshReport};
+
+    /// Unit cube with every side split into `n` x `n` quads.
+    fn tessellated_cube(n: usize) -> Triangles {
+        let sides: [[Vec3; 3]; 6] = [
+            [
+                (0., 0., 0.).into(),
+                (0., 1., 0.).into(),
+                (1., 0., 0.).into(),
+            ],
+            [
+                (0., 0., 1.).into(),
+                (1., 0., 0.).into(),
+                (0., 1., 0.).into(),
+            ],
+            [
+                (0., 0., 0.).into(),
+                (1., 0., 0.).into(),
+                (0., 0., 1.).into(),
+            ],
+            [
+                (0., 1., 0.).into(),
+                (0., 0., 1.).into(),
+                (1., 0., 0.).into(),
+            ],
+            [
+                (0., 0., 0.).into(),
+                (0., 0., 1.).into(),
+                (0., 1., 0.).into(),
+            ],
+            [
+                (1., 0., 0.).into(),
+                (0., 1., 0.).into(),
+                (0., 0., 1.).into(),
+            ],
+        ];
+        let mut triangles = Vec::new();
+        for [o, u, v] in sides {
+            let p = |i: usize, j: usize| o + u * (i as f32 / n as f32) + v * (j as f32 / n as f32);
+            for i in 0..n {
+                for j in 0..n {
+                    triangles.push(Triangle::from_vertices([
+                        p(i, j),
+                        p(i + 1, j),
+                        p(i + 1, j + 1),
+                    ]));
+                    triangles.push(Triangle::from_vertices([
+                        p(i, j),
+                        p(i + 1, j + 1),
+                        p(i, j + 1),
+                    ]));
+                }
+            }
+        }
+        triangles
+    }
+
+    #[test]
+    fn test_flat_regions_collapse_without_error() {
+        let mut triangles = tessellated_cube(8);
+        let before = triangles.len();
+        let options = DecimateOptions {
+            target_facets: None,
+            max_error: Some(1e-9),
+        };
+        let removed = decimate(&mut triangles, &options);
+
+        assert_eq!(before - removed, triangles.len());
+        assert!(triangles.len() < before / 4);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert!((MassProperties::new(&triangles, 1.0).volume - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_target_facet_count() {
+        let mut triangles = tessellated_cube(8);
+        let options = DecimateOptions {
+            target_facets: Some(100),
+            max_error: None,
+        };
+        decimate(&mut triangles, &options);
+
+        assert!(triangles.len() <= 100);
+        assert!(MeshReport::new(&triangles).is_watertight());
+    }
+
+    #[test]
+    fn test_open_border_is_kept() {
+        let mut triangles: Triangles = tessellated_cube(4).into_iter().skip(32).collect();
+        let border = |triangles: &Triangles| {
+            IndexedMesh::from_triangles(triangles)
+                .boundary_loops()
+                .concat()
+                .len()
+        };
+        let before = border(&triangles);
+        decimate(&mut triangles, &DecimateOptions::default());
+        assert_eq!(border(&triangles), before);
+    }
+}
@@ -0,0 +1,450 @@
+use std::{collections::HashMap, fmt};
+
+use super::{
+    bounding_box::Aabb,
+    mass_properties::MassProperties,
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+/// Stored normals further apart than this count as changed, about 0.06°.
+const NORMAL_TOLERANCE: Float = 1e-3;
+
+/// Listed facets per kind in the text report.
+const LISTED: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Same vertices, opposite winding.
+    Flipped,
+    /// Same vertices and winding, different stored normal.
+    Normal,
+    /// Two vertices in common, the third one moved.
+    Moved,
+}
+
+impl ChangeKind {
+    fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Flipped => "flipped",
+            ChangeKind::Normal => "normal",
+            ChangeKind::Moved => "moved",
+        }
+    }
+}
+
+/// Facet `a` of the first mesh that became facet `b` of the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FacetChange {
+    pub a: usize,
+    pub b: usize,
+    pub kind: ChangeKind,
+}
+
+/// Differences between two meshes, independent of the facet order and of the
+/// vertex rotation within facets.
+#[derive(Debug, Clone)]
+pub struct StlDiff {
+    pub headers: [Option<String>; 2],
+    pub facets: [usize; 2],
+    /// Facets present in both meshes.
+    pub unchanged: usize,
+    /// Indices into the first mesh.
+    pub removed: Vec<usize>,
+    /// Indices into the second mesh.
+    pub added: Vec<usize>,
+    pub changed: Vec<FacetChange>,
+    pub bounding_boxes: [Aabb; 2],
+    pub volumes: [f64; 2],
+    a: Triangles,
+    b: Triangles,
+}
+
+impl StlDiff {
+    /// Matches the facets of `a` and `b`. Vertices match when no coordinate
+    /// differs by more than `tolerance`.
+    pub fn new(
+        a: &Triangles,
+        b: &Triangles,
+        headers: [Option<String>; 2],
+        tolerance: Float,
+    ) -> Self {
+        let cell = tolerance.max(1e-9) as f64 * 2.0;
+        let key = |p: Vec3| [p.x, p.y, p.z].map(|c| (c as f64 / cell).floor() as i64);
+        let neighbours = |k: [i64; 3]| {
+            (0..27).map(move |n| [k[0] + n % 3 - 1, k[1] + n / 3 % 3 - 1, k[2] + n / 9 - 1])
+        };
+        let close = |p: Vec3, q: Vec3| {
+            (p.x - q.x).abs() <= tolerance
+                && (p.y - q.y).abs() <= tolerance
+                && (p.z - q.z).abs() <= tolerance
+        };
+        // rotation of `t`'s vertices onto `u`'s, in either winding
+        let same = |t: &Triangle, u: &Triangle, flipped: bool| {
+            let mut v = u.vertices;
+            if flipped {
+                v.swap(1, 2);
+            }
+            (0..3).any(|r| (0..3).all(|i| close(t.vertices[i], v[(i + r) % 3])))
+        };
+
+        let mut by_centroid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for (n, t) in b.iter().enumerate() {
+            by_centroid.entry(key(centroid(t))).or_default().push(n);
+        }
+
+        let mut used = vec![false; b.len()];
+        let mut unchanged = 0;
+        let mut changed = Vec::new();
+        let mut unmatched = Vec::new();
+        for (n, t) in a.iter().enumerate() {
+            let candidates: Vec<usize> = neighbours(key(centroid(t)))
+                .filter_map(|k| by_centroid.get(&k))
+                .flatten()
+                .copied()
+                .filter(|&m| !used[m])
+                .collect();
+            let found = [false, true].into_iter().find_map(|flipped| {
+                candidates
+                    .iter()
+                    .find(|&&m| same(t, &b[m], flipped))
+                    .map(|&m| (m, flipped))
+            });
+            match found {
+                Some((m, flipped)) => {
+                    used[m] = true;
+                    let kind = if flipped {
+                        Some(ChangeKind::Flipped)
+                    } else if (t.normal - b[m].normal).d3() > NORMAL_TOLERANCE {
+                        Some(ChangeKind::Normal)
+                    } else {
+                        None
+                    };
+                    match kind {
+                        Some(kind) => changed.push(FacetChange { a: n, b: m, kind }),
+                        None => unchanged += 1,
+                    }
+                }
+                None => unmatched.push(n),
+            }
+        }
+
+        // facets that kept two vertices
+        let mut by_vertex: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for m in (0..b.len()).filter(|&m| !used[m]) {
+            for v in b[m].vertices {
+                by_vertex.entry(key(v)).or_default().push(m);
+            }
+        }
+        let mut removed = Vec::new();
+        for n in unmatched {
+            let t = &a[n];
+            let mut candidates: Vec<usize> = t
+                .vertices
+                .iter()
+                .flat_map(|&v| neighbours(key(v)))
+                .filter_map(|k| by_vertex.get(&k))
+                .flatten()
+                .copied()
+                .filter(|&m| !used[m])
+                .collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+            let shared = |m: usize| {
+                t.vertices
+                    .iter()
+                    .filter(|&&p| b[m].vertices.iter().any(|&q| close(p, q)))
+                    .count()
+            };
+            match candidates.into_iter().find(|&m| shared(m) >= 2) {
+                Some(m) => {
+                    used[m] = true;
+                    changed.push(FacetChange {
+                        a: n,
+                        b: m,
+                        kind: ChangeKind::Moved,
+                    });
+                }
+                None => removed.push(n),
+            }
+        }
+        changed.sort_by_key(|change| change.a);
+
+        StlDiff {
+            headers,
+            facets: [a.len(), b.len()],
+            unchanged,
+            removed,
+            added: (0..b.len()).filter(|&m| !used[m]).collect(),
+            changed,
+            bounding_boxes: [Aabb::from_triangles(a), Aabb::from_triangles(b)],
+            volumes: [
+                MassProperties::new(a, 1.0).volume,
+                MassProperties::new(b, 1.0).volume,
+            ],
+            a: a.clone(),
+            b: b.clone(),
+        }
+    }
+
+    /// No facet was added, removed or changed. Headers are not compared.
+    pub fn is_identical(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+
+    /// The report as a JSON object, with the vertices of all differing facets.
+    pub fn to_json(&self) -> String {
+        let point = |v: Vec3| {
+            format!(
+                "[{},{},{}]",
+                json_number(v.x),
+                json_number(v.y),
+                json_number(v.z)
+            )
+        };
+        let facet = |t: &Triangle| {
+            format!(
+                "[{},{},{}]",
+                point(t.vertices[0]),
+                point(t.vertices[1]),
+                point(t.vertices[2])
+            )
+        };
+        let header = |h: &Option<String>| h.as_deref().map_or("null".to_string(), json_string);
+        let aabb = |b: &Aabb| {
+            if b.is_empty() {
+                "null".to_string()
+            } else {
+                format!(r#"{{"min":{},"max":{}}}"#, point(b.min), point(b.max))
+            }
+        };
+        let list = |indices: &[usize], triangles: &Triangles| {
+            let items: Vec<String> = indices
+                .iter()
+                .map(|&n| format!(r#"{{"index":{},"vertices":{}}}"#, n, facet(&triangles[n])))
+                .collect();
+            format!("[{}]", items.join(","))
+        };
+        let changed: Vec<String> = self
+            .changed
+            .iter()
+            .map(|change| {
+                format!(
+                    r#"{{"kind":"{}","a":{},"b":{},"before":{},"after":{}}}"#,
+                    change.kind.name(),
+                    change.a,
+                    change.b,
+                    facet(&self.a[change.a]),
+                    facet(&self.b[change.b])
+                )
+            })
+            .collect();
+
+        format!(
+            concat!(
+                "{{",
+                r#""identical":{},"#,
+                r#""headers":{{"a":{},"b":{}}},"#,
+                r#""facets":{{"a":{},"b":{}}},"#,
+                r#""unchanged":{},"#,
+                r#""removed":{},"#,
+                r#""added":{},"#,
+                r#""changed":[{}],"#,
+                r#""bounding_box":{{"a":{},"b":{}}},"#,
+                r#""volume":{{"a":{},"b":{}}}"#,
+                "}}"
+            ),
+            self.is_identical(),
+            header(&self.headers[0]),
+            header(&self.headers[1]),
+            self.facets[0],
+            self.facets[1],
+            self.unchanged,
+            list(&self.removed, &self.a),
+            list(&self.added, &self.b),
+            changed.join(","),
+            aabb(&self.bounding_boxes[0]),
+            aabb(&self.bounding_boxes[1]),
+            json_number(self.volumes[0]),
+            json_number(self.volumes[1]),
+        )
+    }
+}
+
+impl fmt::Display for StlDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let point = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        let facet = |t: &Triangle| t.vertices.map(point).join(", ");
+
+        if self.headers[0] != self.headers[1] {
+            let header =
+                |h: &Option<String>| h.as_deref().map_or("-".to_string(), |h| format!("{:?}", h));
+            writeln!(
+                f,
+                "header:    {} -> {}",
+                header(&self.headers[0]),
+                header(&self.headers[1])
+            )?;
+        }
+        writeln!(
+            f,
+            "facets:    {} -> {} ({:+})",
+            self.facets[0],
+            self.facets[1],
+            self.facets[1] as i64 - self.facets[0] as i64
+        )?;
+        writeln!(f, "unchanged: {}", self.unchanged)?;
+        writeln!(f, "removed:   {}", self.removed.len())?;
+        writeln!(f, "added:     {}", self.added.len())?;
+        writeln!(f, "changed:   {}", self.changed.len())?;
+
+        let [a, b] = &self.bounding_boxes;
+        if a != b && !a.is_empty() && !b.is_empty() {
+            writeln!(f, "bounding box min: {} -> {}", point(a.min), point(b.min))?;
+            writeln!(f, "bounding box max: {} -> {}", point(a.max), point(b.max))?;
+        }
+        writeln!(
+            f,
+            "volume:    {} -> {} ({:+})",
+            self.volumes[0],
+            self.volumes[1],
+            self.volumes[1] - self.volumes[0]
+        )?;
+
+        for &n in self.removed.iter().take(LISTED) {
+            writeln!(f, "- {}: {}", n, facet(&self.a[n]))?;
+        }
+        for &m in self.added.iter().take(LISTED) {
+            writeln!(f, "+ {}: {}", m, facet(&self.b[m]))?;
+        }
+        for change in self.changed.iter().take(LISTED) {
+            writeln!(
+                f,
+                "~ {} -> {} {}: {}",
+                change.a,
+                change.b,
+                change.kind.name(),
+                facet(&self.b[change.b])
+            )?;
+        }
+        let hidden = [self.removed.len(), self.added.len(), self.changed.len()]
+            .iter()
+            .map(|n| n.saturating_sub(LISTED))
+            .sum::<usize>();
+        if hidden > 0 {
+            writeln!(f, "... {} more facets, use --json for all", hidden)?;
+        }
+        Ok(())
+    }
+}
+
+fn centroid(t: &Triangle) -> Vec3 {
+    (t.vertices[0] + t.vertices[1] + t.vertices[2]) / 3.0
+}
+
+/// JSON has no NaN or infinity.
+fn json_number<T: Into<f64>>(x: T) -> String {
+    let x = x.into();
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    #[test]
+    fn test_reordered_is_identical() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let mut b = a.clone();
+        b.reverse();
+        for t in b.iter_mut() {
+            t.vertices.rotate_left(1);
+            t.vertices[0].x += 1e-7;
+        }
+
+        let diff = StlDiff::new(&a, &b, [None, None], 1e-6);
+        assert!(diff.is_identical());
+        assert_eq!(diff.unchanged, 12);
+        assert!(diff.to_json().starts_with(r#"{"identical":true,"#));
+
+        let strict = StlDiff::new(&a, &b, [None, None], 0.0);
+        assert!(!strict.is_identical());
+    }
+
+    #[test]
+    fn test_changes() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let mut b = a.clone();
+        b[0].flip();
+        b[1].vertices[2].z -= 0.5;
+        b[3].normal = Vec3::from((0.0, 0.0, 0.0));
+        b.remove(2);
+        b.push(Triangle::from_vertices([
+            (5.0, 5.0, 5.0).into(),
+            (6.0, 5.0, 5.0).into(),
+            (5.0, 6.0, 5.0).into(),
+        ]));
+        b.swap(0, 7);
+
+        let diff = StlDiff::new(&a, &b, [Some("a".into()), Some("b".into())], 1e-6);
+        assert_eq!(diff.facets, [12, 12]);
+        assert_eq!(diff.unchanged, 8);
+        assert_eq!(diff.removed, vec![2]);
+        assert_eq!(diff.added, vec![11]);
+        let kinds: Vec<(usize, ChangeKind)> = diff.changed.iter().map(|c| (c.a, c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, ChangeKind::Flipped),
+                (1, ChangeKind::Moved),
+                (3, ChangeKind::Normal)
+            ]
+        );
+        assert_eq!(diff.changed[0].b, 7);
+        assert_eq!(diff.bounding_boxes[1].max, Vec3::from((6.0, 6.0, 5.0)));
+        assert!((diff.volumes[0] - 1.0).abs() < 1e-6);
+
+        let text = diff.to_string();
+        assert!(text.contains("header:    \"a\" -> \"b\""));
+        assert!(text.contains("removed:   1"));
+        assert!(text.contains("~ 0 -> 7 flipped"));
+    }
+
+    #[test]
+    fn test_json() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let b = a[..11].to_vec();
+        let diff = StlDiff::new(&a, &b, [Some("say \"hi\"\n".into()), None], 1e-6);
+        let json = diff.to_json();
+        assert!(json.contains(r#""headers":{"a":"say \"hi\"\n","b":null}"#));
+        assert!(json.contains(r#""facets":{"a":12,"b":11}"#));
+        assert!(json.contains(r#""removed":[{"index":11,"vertices":[[1,0,0],"#));
+        assert!(json.contains(r#""added":[]"#));
+        assert!(json.contains(r#""bounding_box":{"a":{"min":[0,0,0],"max":[1,1,1]}"#));
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json_number(Float::NAN), "null");
+    }
+}
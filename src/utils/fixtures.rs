@@ -0,0 +1,47 @@
+//! Meshes shared by the unit tests.
+
+use super::{
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+/// Axis aligned box with outward facing facets.
+pub fn cuboid(min: Vec3, max: Vec3) -> Triangles {
+    let p = |i: usize| {
+        Vec3::from((
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        ))
+    };
+    [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ]
+    .iter()
+    .flat_map(|q| {
+        [
+            Triangle::from_vertices([p(q[0]), p(q[1]), p(q[2])]),
+            Triangle::from_vertices([p(q[0]), p(q[2]), p(q[3])]),
+        ]
+    })
+    .collect()
+}
+
+/// Outward oriented unit corner tetrahedron, moved by `offset` along x.
+pub fn tetrahedron(offset: Float) -> Triangles {
+    let p: [Vec3; 4] = [
+        (offset, 0.0, 0.0).into(),
+        (offset + 1.0, 0.0, 0.0).into(),
+        (offset, 1.0, 0.0).into(),
+        (offset, 0.0, 1.0).into(),
+    ];
+    [[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]]
+        .iter()
+        .map(|f| Triangle::from_vertices(f.map(|i| p[i])))
+        .collect()
+}
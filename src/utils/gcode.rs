@@ -0,0 +1,742 @@
+use std::{
+    f64::consts::PI,
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context};
+
+use super::{
+    bounding_box::Aabb,
+    matrix::Mat4,
+    slicer::{slice_layers, Contour, Point2},
+    stl_parser::Triangles,
+    transform::transform,
+    vec3::{Float, Vec3},
+};
+
+/// Printer and print settings, all lengths in millimetres and speeds in mm/s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintSettings {
+    pub nozzle_diameter: f64,
+    pub filament_diameter: f64,
+    pub layer_height: f64,
+    pub extrusion_width: f64,
+    pub extrusion_multiplier: f64,
+    pub perimeters: usize,
+    /// Fraction of the area covered by sparse infill, 0 to 1.
+    pub infill_density: f64,
+    /// Direction of the infill lines in degrees, turned by 90 every layer.
+    pub infill_angle: f64,
+    /// Overlap of infill and innermost perimeter as a fraction of the width,
+    /// 0 to 0.5.
+    pub infill_overlap: f64,
+    /// Number of bottom and top layers printed with solid infill.
+    pub solid_layers: usize,
+    pub print_speed: f64,
+    pub first_layer_speed: f64,
+    pub travel_speed: f64,
+    pub retraction_length: f64,
+    pub retraction_speed: f64,
+    /// Travel moves shorter than this do not retract.
+    pub retraction_min_travel: f64,
+    pub nozzle_temperature: f64,
+    pub bed_temperature: f64,
+    /// Part cooling fan from the second layer on, 0 to 255.
+    pub fan_speed: u8,
+    /// Size of the bed along x; the part is centred on the bed.
+    pub bed_width: f64,
+    /// Size of the bed along y.
+    pub bed_depth: f64,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        PrintSettings {
+            nozzle_diameter: 0.4,
+            filament_diameter: 1.75,
+            layer_height: 0.2,
+            extrusion_width: 0.45,
+            extrusion_multiplier: 1.0,
+            perimeters: 2,
+            infill_density: 0.2,
+            infill_angle: 45.0,
+            infill_overlap: 0.15,
+            solid_layers: 3,
+            print_speed: 50.0,
+            first_layer_speed: 20.0,
+            travel_speed: 150.0,
+            retraction_length: 1.0,
+            retraction_speed: 40.0,
+            retraction_min_travel: 2.0,
+            nozzle_temperature: 210.0,
+            bed_temperature: 60.0,
+            fan_speed: 255,
+            bed_width: 220.0,
+            bed_depth: 220.0,
+        }
+    }
+}
+
+impl FromStr for PrintSettings {
+    type Err = anyhow::Error;
+
+    /// Reads `key = value` lines on top of the defaults. Empty lines and
+    /// everything after `#` or `;` are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = PrintSettings::default();
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split(['#', ';']).next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected 'key = value' on line {}!", n + 1))?;
+            settings
+                .set(key.trim(), value.trim())
+                .with_context(|| format!("Invalid setting on line {}!", n + 1))?;
+        }
+        settings.validate()?;
+        Ok(settings)
+    }
+}
+
+impl PrintSettings {
+    pub fn from_file(path: PathBuf) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read settings \'{}\'!", path.display()))?;
+        text.parse()
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        fn parse<T: FromStr>(value: &str) -> anyhow::Result<T> {
+            value
+                .parse()
+                .map_err(|_| anyhow!("Cannot parse '{}'!", value))
+        }
+
+        match key {
+            "nozzle_diameter" => self.nozzle_diameter = parse(value)?,
+            "filament_diameter" => self.filament_diameter = parse(value)?,
+            "layer_height" => self.layer_height = parse(value)?,
+            "extrusion_width" => self.extrusion_width = parse(value)?,
+            "extrusion_multiplier" => self.extrusion_multiplier = parse(value)?,
+            "perimeters" => self.perimeters = parse(value)?,
+            "infill_density" => self.infill_density = parse(value)?,
+            "infill_angle" => self.infill_angle = parse(value)?,
+            "infill_overlap" => self.infill_overlap = parse(value)?,
+            "solid_layers" => self.solid_layers = parse(value)?,
+            "print_speed" => self.print_speed = parse(value)?,
+            "first_layer_speed" => self.first_layer_speed = parse(value)?,
+            "travel_speed" => self.travel_speed = parse(value)?,
+            "retraction_length" => self.retraction_length = parse(value)?,
+            "retraction_speed" => self.retraction_speed = parse(value)?,
+            "retraction_min_travel" => self.retraction_min_travel = parse(value)?,
+            "nozzle_temperature" => self.nozzle_temperature = parse(value)?,
+            "bed_temperature" => self.bed_temperature = parse(value)?,
+            "fan_speed" => self.fan_speed = parse(value)?,
+            "bed_width" => self.bed_width = parse(value)?,
+            "bed_depth" => self.bed_depth = parse(value)?,
+            _ => return Err(anyhow!("Unknown setting '{}'!", key)),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let positive = [
+            ("nozzle_diameter", self.nozzle_diameter),
+            ("filament_diameter", self.filament_diameter),
+            ("layer_height", self.layer_height),
+            ("extrusion_width", self.extrusion_width),
+            ("extrusion_multiplier", self.extrusion_multiplier),
+            ("print_speed", self.print_speed),
+            ("first_layer_speed", self.first_layer_speed),
+            ("travel_speed", self.travel_speed),
+            ("retraction_speed", self.retraction_speed),
+            ("bed_width", self.bed_width),
+            ("bed_depth", self.bed_depth),
+        ];
+        if let Some((key, _)) = positive
+            .iter()
+            .find(|(_, value)| value.is_nan() || *value <= 0.0)
+        {
+            return Err(anyhow!("Setting '{}' must be positive!", key));
+        }
+        if self.retraction_min_travel.is_nan() || self.retraction_min_travel < 0.0 {
+            return Err(anyhow!(
+                "Setting 'retraction_min_travel' must not be negative!"
+            ));
+        }
+        let ranges = [
+            ("infill_density", self.infill_density, 0.0, 1.0),
+            // more would move the infill past the middle of the perimeter
+            ("infill_overlap", self.infill_overlap, 0.0, 0.5),
+            ("retraction_length", self.retraction_length, 0.0, 20.0),
+            ("nozzle_temperature", self.nozzle_temperature, 0.0, 450.0),
+            ("bed_temperature", self.bed_temperature, 0.0, 150.0),
+        ];
+        if let Some((key, _, min, max)) = ranges
+            .iter()
+            .find(|(_, value, min, max)| !(min..=max).contains(&value))
+        {
+            return Err(anyhow!(
+                "Setting '{}' must be between {} and {}!",
+                key,
+                min,
+                max
+            ));
+        }
+        if !self.infill_angle.is_finite() {
+            return Err(anyhow!("Setting 'infill_angle' must be a number!"));
+        }
+        Ok(())
+    }
+
+    /// Filament length per millimetre of extruded line.
+    fn extrusion_per_mm(&self) -> f64 {
+        let filament_area = PI * (self.filament_diameter / 2.0).powi(2);
+        self.extrusion_width * self.layer_height / filament_area * self.extrusion_multiplier
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    Perimeter,
+    Infill,
+}
+
+/// Line the nozzle extrudes along.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolPath {
+    pub kind: PathKind,
+    pub points: Vec<Point2>,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerPaths {
+    /// Nozzle height above the bed.
+    pub z: f64,
+    pub paths: Vec<ToolPath>,
+}
+
+/// Slices the mesh and plans perimeters and infill for every layer.
+///
+/// The mesh is centred on the bed in x and y, with its lowest point on the
+/// bed.
+pub fn plan_layers(triangles: &Triangles, settings: &PrintSettings) -> Vec<LayerPaths> {
+    let layers = slice_layers(
+        &place_on_bed(triangles, settings),
+        Vec3::from((0.0, 0.0, 1.0)),
+        settings.layer_height,
+    );
+    let count = layers.len();
+    let width = settings.extrusion_width;
+
+    layers
+        .iter()
+        .enumerate()
+        .map(|(i, layer)| {
+            let contours: Vec<Vec<Point2>> = layer
+                .contours
+                .iter()
+                .filter(|contour| contour.closed)
+                .map(|contour| simplify(&contour.points, width / 20.0))
+                .filter(|points| points.len() >= 3)
+                .collect();
+
+            let mut paths = Vec::new();
+            for k in (0..settings.perimeters).rev() {
+                let distance = (k as f64 + 0.5) * width;
+                for contour in &contours {
+                    if let Some(points) = offset_contour(contour, distance, width) {
+                        paths.push(ToolPath {
+                            kind: PathKind::Perimeter,
+                            points,
+                            closed: true,
+                        });
+                    }
+                }
+            }
+
+            let solid = i < settings.solid_layers || i + settings.solid_layers >= count;
+            let density = if solid { 1.0 } else { settings.infill_density };
+            if density > 0.0 {
+                let inset = (settings.perimeters as f64 + 0.5 - settings.infill_overlap) * width;
+                let boundary: Vec<Vec<Point2>> = contours
+                    .iter()
+                    .filter_map(|contour| offset_contour(contour, inset, width))
+                    .collect();
+                let angle = settings.infill_angle + if i % 2 == 0 { 0.0 } else { 90.0 };
+                for [a, b] in rectilinear_infill(&boundary, width / density, angle) {
+                    paths.push(ToolPath {
+                        kind: PathKind::Infill,
+                        points: vec![a, b],
+                        closed: false,
+                    });
+                }
+            }
+
+            LayerPaths {
+                z: (i + 1) as f64 * settings.layer_height,
+                paths,
+            }
+        })
+        .collect()
+}
+
+/// Copy of the mesh moved to the centre of the bed, resting on it.
+fn place_on_bed(triangles: &Triangles, settings: &PrintSettings) -> Triangles {
+    let bounds = Aabb::from_triangles(triangles);
+    let mut placed = triangles.clone();
+    if bounds.is_empty() {
+        return placed;
+    }
+    let center = bounds.center();
+    let offset = Vec3::from((
+        (settings.bed_width / 2.0) as Float - center.x,
+        (settings.bed_depth / 2.0) as Float - center.y,
+        -bounds.min.z,
+    ));
+    transform(&mut placed, &Mat4::translation(offset));
+    placed
+}
+
+/// Whether the footprint of the mesh fits onto the bed.
+pub fn fits_on_bed(triangles: &Triangles, settings: &PrintSettings) -> bool {
+    let size = Aabb::from_triangles(triangles).size();
+    size.x as f64 <= settings.bed_width && size.y as f64 <= settings.bed_depth
+}
+
+fn signed_area(points: &[Point2]) -> f64 {
+    Contour {
+        points: points.to_vec(),
+        closed: true,
+        is_hole: false,
+        parent: None,
+    }
+    .signed_area()
+}
+
+/// Drops points closer than `tolerance` to their predecessor or to the line
+/// through their neighbours.
+fn simplify(points: &[Point2], tolerance: f64) -> Vec<Point2> {
+    let mut result: Vec<Point2> = Vec::with_capacity(points.len());
+    for &p in points {
+        if let Some(&last) = result.last() {
+            if distance(last, p) < tolerance {
+                continue;
+            }
+        }
+        result.push(p);
+    }
+    while result.len() > 2 && distance(result[0], result[result.len() - 1]) < tolerance {
+        result.pop();
+    }
+
+    let mut i = 0;
+    while result.len() > 3 && i < result.len() {
+        let n = result.len();
+        let (a, p, b) = (result[(i + n - 1) % n], result[i], result[(i + 1) % n]);
+        let cross = (p[0] - a[0]) * (b[1] - a[1]) - (p[1] - a[1]) * (b[0] - a[0]);
+        if cross.abs() / distance(a, b).max(f64::MIN_POSITIVE) < tolerance {
+            result.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+fn distance(a: Point2, b: Point2) -> f64 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
+
+/// Moves every edge of the contour `distance` to its left, into the material
+/// for counter-clockwise outlines and clockwise holes.
+///
+/// Corners are mitred, with the mitre capped at three times the distance.
+/// Returns `None` when the contour collapses, i.e. its orientation flips or
+/// it becomes thinner than `min_width`.
+pub fn offset_contour(points: &[Point2], distance: f64, min_width: f64) -> Option<Vec<Point2>> {
+    let n = points.len();
+    let normal = |i: usize| {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        let length = self::distance(a, b);
+        [-(b[1] - a[1]) / length, (b[0] - a[0]) / length]
+    };
+
+    let offset: Vec<Point2> = (0..n)
+        .map(|i| {
+            let (n1, n2) = (normal((i + n - 1) % n), normal(i));
+            let denominator = 1.0 + n1[0] * n2[0] + n1[1] * n2[1];
+            let mut miter = [
+                (n1[0] + n2[0]) / denominator.max(1e-9),
+                (n1[1] + n2[1]) / denominator.max(1e-9),
+            ];
+            let length = miter[0].hypot(miter[1]);
+            if length > 3.0 {
+                miter = [miter[0] * 3.0 / length, miter[1] * 3.0 / length];
+            }
+            [
+                points[i][0] + miter[0] * distance,
+                points[i][1] + miter[1] * distance,
+            ]
+        })
+        .collect();
+
+    let (before, after) = (signed_area(points), signed_area(&offset));
+    let perimeter: f64 = (0..n)
+        .map(|i| self::distance(offset[i], offset[(i + 1) % n]))
+        .sum();
+    if before.signum() != after.signum() || 2.0 * after.abs() / perimeter < min_width / 2.0 {
+        return None;
+    }
+    Some(offset)
+}
+
+/// Parallel lines `spacing` apart at `angle` degrees, clipped to the inside of
+/// the contours with the even-odd rule. Consecutive lines alternate their
+/// direction to keep travel moves short.
+pub fn rectilinear_infill(contours: &[Vec<Point2>], spacing: f64, angle: f64) -> Vec<[Point2; 2]> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let rotate = |p: Point2| [p[0] * cos + p[1] * sin, -p[0] * sin + p[1] * cos];
+    let unrotate = |p: Point2| [p[0] * cos - p[1] * sin, p[0] * sin + p[1] * cos];
+
+    let rotated: Vec<Vec<Point2>> = contours
+        .iter()
+        .map(|contour| contour.iter().map(|&p| rotate(p)).collect())
+        .collect();
+    let (min, max) = rotated
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+            (min.min(p[1]), max.max(p[1]))
+        });
+    if min > max {
+        return Vec::new();
+    }
+
+    let mut lines = Vec::new();
+    let mut row = (min / spacing).ceil() as i64;
+    while row as f64 * spacing <= max {
+        let y = row as f64 * spacing;
+        let mut xs: Vec<f64> = rotated
+            .iter()
+            .flat_map(|contour| {
+                let n = contour.len();
+                (0..n).filter_map(move |i| {
+                    let (a, b) = (contour[i], contour[(i + 1) % n]);
+                    ((a[1] > y) != (b[1] > y))
+                        .then(|| a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+                })
+            })
+            .collect();
+        xs.sort_by(f64::total_cmp);
+
+        let mut segments: Vec<[Point2; 2]> = xs
+            .chunks_exact(2)
+            .map(|pair| [unrotate([pair[0], y]), unrotate([pair[1], y])])
+            .collect();
+        if row % 2 != 0 {
+            segments.reverse();
+            segments.iter_mut().for_each(|segment| segment.reverse());
+        }
+        lines.extend(segments);
+        row += 1;
+    }
+    lines
+}
+
+/// Totals of an emitted program.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GcodeStats {
+    pub layers: usize,
+    /// Filament pushed through the nozzle, in millimetres.
+    pub filament: f64,
+    /// Length of all extruded lines.
+    pub extruded: f64,
+}
+
+/// Writes the program for `layers` to a file.
+pub fn write_gcode_file(
+    path: PathBuf,
+    layers: &[LayerPaths],
+    settings: &PrintSettings,
+) -> anyhow::Result<GcodeStats> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path.clone())
+        .with_context(|| {
+            format!(
+                "Failed to create a file for writing!\nPath: \'{}\'",
+                path.display()
+            )
+        })?;
+    let mut writer = BufWriter::new(file);
+    let stats = write_gcode(&mut writer, layers, settings)?;
+    writer.flush()?;
+    Ok(stats)
+}
+
+/// Writes a Marlin flavoured program: absolute positioning, absolute
+/// extrusion reset at every layer, firmware independent retraction.
+pub fn write_gcode<W: Write>(
+    writer: &mut W,
+    layers: &[LayerPaths],
+    settings: &PrintSettings,
+) -> anyhow::Result<GcodeStats> {
+    let mut stats = GcodeStats {
+        layers: layers.len(),
+        ..Default::default()
+    };
+    let per_mm = settings.extrusion_per_mm();
+    let travel = settings.travel_speed * 60.0;
+    let retract = settings.retraction_speed * 60.0;
+
+    writeln!(writer, "; generated by stl_parser")?;
+    writeln!(
+        writer,
+        "; layer height {} mm, {} layers",
+        settings.layer_height,
+        layers.len()
+    )?;
+    writeln!(writer, "M140 S{}", settings.bed_temperature)?;
+    writeln!(writer, "M104 S{}", settings.nozzle_temperature)?;
+    writeln!(writer, "M190 S{}", settings.bed_temperature)?;
+    writeln!(writer, "M109 S{}", settings.nozzle_temperature)?;
+    writeln!(writer, "G21 ; millimetres")?;
+    writeln!(writer, "G90 ; absolute positioning")?;
+    writeln!(writer, "M82 ; absolute extrusion")?;
+    writeln!(writer, "G28 ; home all axes")?;
+    writeln!(writer, "G92 E0")?;
+
+    let mut position: Option<Point2> = None;
+    for (i, layer) in layers.iter().enumerate() {
+        let speed = if i == 0 {
+            settings.first_layer_speed
+        } else {
+            settings.print_speed
+        } * 60.0;
+
+        writeln!(writer, ";LAYER:{}", i)?;
+        if i == 1 && settings.fan_speed > 0 {
+            writeln!(writer, "M106 S{}", settings.fan_speed)?;
+        }
+        writeln!(writer, "G92 E0")?;
+        let mut e = 0.0;
+        writeln!(writer, "G1 Z{:.3} F{:.0}", layer.z, travel)?;
+
+        for path in &layer.paths {
+            let start = path.points[0];
+            let far = position.map_or(f64::INFINITY, |p| distance(p, start))
+                >= settings.retraction_min_travel;
+            let retracting = far && settings.retraction_length > 0.0;
+            if retracting {
+                writeln!(
+                    writer,
+                    "G1 E{:.5} F{:.0}",
+                    e - settings.retraction_length,
+                    retract
+                )?;
+            }
+            writeln!(
+                writer,
+                "G0 X{:.3} Y{:.3} F{:.0}",
+                start[0], start[1], travel
+            )?;
+            if retracting {
+                writeln!(writer, "G1 E{:.5} F{:.0}", e, retract)?;
+            }
+
+            let mut points = path.points.clone();
+            if path.closed {
+                points.push(start);
+            }
+            let mut last = start;
+            for &p in &points[1..] {
+                let length = distance(last, p);
+                e += length * per_mm;
+                stats.extruded += length;
+                writeln!(
+                    writer,
+                    "G1 X{:.3} Y{:.3} E{:.5} F{:.0}",
+                    p[0], p[1], e, speed
+                )?;
+                last = p;
+            }
+            position = Some(last);
+        }
+        stats.filament += e;
+    }
+
+    writeln!(writer, "; end")?;
+    writeln!(writer, "M107")?;
+    writeln!(writer, "M104 S0")?;
+    writeln!(writer, "M140 S0")?;
+    writeln!(writer, "G91")?;
+    writeln!(writer, "G1 Z10 F{:.0}", travel)?;
+    writeln!(writer, "G90")?;
+    writeln!(writer, "M84")?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    fn square(size: f64) -> Vec<Point2> {
+        vec![[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]]
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let settings: PrintSettings = "
+            # printer
+            nozzle_diameter = 0.6
+            layer_height=0.3 ; coarse
+            perimeters = 3
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(settings.nozzle_diameter, 0.6);
+        assert_eq!(settings.layer_height, 0.3);
+        assert_eq!(settings.perimeters, 3);
+        assert_eq!(settings.print_speed, PrintSettings::default().print_speed);
+
+        assert!("speed = 3".parse::<PrintSettings>().is_err());
+        assert!("perimeters = two".parse::<PrintSettings>().is_err());
+        assert!("layer_height".parse::<PrintSettings>().is_err());
+        assert!("layer_height = 0".parse::<PrintSettings>().is_err());
+    }
+
+    #[test]
+    fn test_settings_out_of_range() {
+        for line in [
+            "extrusion_multiplier = -1",
+            "extrusion_multiplier = 0",
+            "infill_overlap = 5",
+            "infill_overlap = -0.1",
+            "retraction_length = -1",
+            "retraction_length = 100",
+            "retraction_speed = 0",
+            "retraction_min_travel = NaN",
+            "nozzle_temperature = -10",
+            "nozzle_temperature = 1000",
+            "bed_temperature = 300",
+            "infill_angle = inf",
+        ] {
+            assert!(line.parse::<PrintSettings>().is_err(), "{}", line);
+        }
+        let settings: PrintSettings = "
+            infill_overlap = 0.5
+            retraction_length = 0
+            nozzle_temperature = 0
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(settings.infill_overlap, 0.5);
+    }
+
+    #[test]
+    fn test_offset_contour() {
+        let inner = offset_contour(&square(10.0), 1.0, 0.4).unwrap();
+        assert_eq!(inner, vec![[1.0, 1.0], [9.0, 1.0], [9.0, 9.0], [1.0, 9.0]]);
+
+        // holes run clockwise and grow
+        let mut hole = square(2.0);
+        hole.reverse();
+        let grown = offset_contour(&hole, 0.5, 0.4).unwrap();
+        assert!((signed_area(&grown) + 9.0).abs() < 1e-9);
+
+        assert!(offset_contour(&square(1.0), 0.6, 0.4).is_none());
+    }
+
+    #[test]
+    fn test_infill_covers_square() {
+        let lines = rectilinear_infill(&[square(10.0)], 1.0, 0.0);
+        assert_eq!(lines.len(), 10);
+        for line in &lines {
+            assert!((distance(line[0], line[1]) - 10.0).abs() < 1e-9);
+        }
+        // zigzag
+        assert_eq!((lines[0][1][0], lines[1][0][0]), (10.0, 10.0));
+
+        let mut hole = square(4.0);
+        hole.iter_mut().for_each(|p| *p = [p[0] + 3.0, p[1] + 3.0]);
+        hole.reverse();
+        let lines = rectilinear_infill(&[square(10.0), hole], 1.0, 0.0);
+        let total: f64 = lines.iter().map(|l| distance(l[0], l[1])).sum();
+        assert!((total - (10.0 * 10.0 - 4.0 * 4.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_gcode() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (10.0, 10.0, 1.0).into());
+
+        let settings = PrintSettings::default();
+        let layers = plan_layers(&triangles, &settings);
+        assert_eq!(layers.len(), 5);
+        assert!((layers[4].z - 1.0).abs() < 1e-9);
+        let perimeters = layers[0]
+            .paths
+            .iter()
+            .filter(|path| path.kind == PathKind::Perimeter)
+            .count();
+        assert_eq!(perimeters, 2);
+
+        let mut buffer = Vec::new();
+        let stats = write_gcode(&mut buffer, &layers, &settings).unwrap();
+        let gcode = String::from_utf8(buffer).unwrap();
+        assert_eq!(gcode.matches(";LAYER:").count(), 5);
+        assert!(gcode.contains("M109 S210"));
+        assert!(gcode.contains("G1 Z0.200"));
+        assert!(stats.filament > 0.0);
+        // solid layers: roughly the whole area is covered with lines
+        let per_layer = stats.extruded / 5.0;
+        assert!(per_layer * settings.extrusion_width > 80.0);
+    }
+
+    #[test]
+    fn test_part_is_placed_on_the_bed() {
+        // modelled around the origin in x and y and floating above z = 0
+        let triangles = cuboid((-15.0, -5.0, 30.0).into(), (5.0, 5.0, 31.0).into());
+        let settings = PrintSettings {
+            bed_width: 200.0,
+            bed_depth: 100.0,
+            ..Default::default()
+        };
+        assert!(fits_on_bed(&triangles, &settings));
+        let layers = plan_layers(&triangles, &settings);
+        assert_eq!(layers.len(), 5);
+        assert!((layers[0].z - 0.2).abs() < 1e-9);
+
+        let points = layers
+            .iter()
+            .flat_map(|layer| &layer.paths)
+            .flat_map(|path| &path.points);
+        for p in points {
+            assert!((90.0..=110.0).contains(&p[0]) && (45.0..=55.0).contains(&p[1]));
+        }
+        // the outer perimeter runs half a width inside the walls
+        let min_x = layers[0]
+            .paths
+            .iter()
+            .flat_map(|path| &path.points)
+            .map(|p| p[0])
+            .fold(f64::INFINITY, f64::min);
+        assert!((min_x - (90.0 + settings.extrusion_width / 2.0)).abs() < 1e-3);
+
+        let narrow = PrintSettings {
+            bed_depth: 5.0,
+            ..settings
+        };
+        assert!(!fits_on_bed(&triangles, &narrow));
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+
+use super::{
+    bvh::Bvh,
+    containment::closest_point,
+    mesh::IndexedMesh,
+    stl_parser::Triangles,
+    vec3::{Float, Vec3},
+};
+
+/// Xorshift generator, so that sampling is reproducible without extra
+/// dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Area weighted random points on the surface, after all vertices.
+pub fn sample_surface(triangles: &Triangles, count: usize, seed: u64) -> Vec<Vec3> {
+    let mut points = IndexedMesh::from_triangles(triangles).vertices;
+    let cumulative: Vec<f64> = triangles
+        .iter()
+        .scan(0.0, |total, t| {
+            *total += t.area() as f64;
+            Some(*total)
+        })
+        .collect();
+    let Some(&total) = cumulative.last().filter(|&&total| total > 0.0) else {
+        return points;
+    };
+
+    let mut rng = Rng::new(seed);
+    for _ in 0..count {
+        let target = rng.next() * total;
+        let facet = cumulative
+            .partition_point(|&area| area <= target)
+            .min(triangles.len() - 1);
+        // uniform in the triangle by folding the unit square
+        let (mut u, mut v) = (rng.next() as Float, rng.next() as Float);
+        if u + v > 1.0 {
+            (u, v) = (1.0 - u, 1.0 - v);
+        }
+        let [a, b, c] = triangles[facet].vertices;
+        points.push(a + (b - a) * u + (c - a) * v);
+    }
+    points
+}
+
+/// Distances from sample points of one mesh to the surface of another.
+///
+/// Samples of a mesh compared against an empty one are infinitely far away;
+/// without any samples all fields are zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    /// One-sided Hausdorff distance.
+    pub max: Float,
+    pub mean: Float,
+    pub rms: Float,
+    pub samples: usize,
+}
+
+impl Deviation {
+    fn new(distances: &[f64]) -> Self {
+        if distances.is_empty() {
+            return Deviation {
+                max: 0.0,
+                mean: 0.0,
+                rms: 0.0,
+                samples: 0,
+            };
+        }
+        let n = distances.len() as f64;
+        Deviation {
+            max: distances.iter().copied().fold(0.0, f64::max) as Float,
+            mean: (distances.iter().sum::<f64>() / n) as Float,
+            rms: (distances.iter().map(|d| d * d).sum::<f64>() / n).sqrt() as Float,
+            samples: distances.len(),
+        }
+    }
+}
+
+/// Deviations in both directions between two meshes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    /// From samples of the first mesh to the second.
+    pub forward: Deviation,
+    /// From samples of the second mesh to the first.
+    pub backward: Deviation,
+}
+
+impl Comparison {
+    /// Symmetric Hausdorff distance.
+    pub fn hausdorff(&self) -> Float {
+        self.forward.max.max(self.backward.max)
+    }
+
+    /// Root mean square over the samples of both directions.
+    pub fn rms(&self) -> Float {
+        let (f, b) = (self.forward, self.backward);
+        let n = (f.samples + b.samples).max(1) as Float;
+        ((f.rms * f.rms * f.samples as Float + b.rms * b.rms * b.samples as Float) / n).sqrt()
+    }
+}
+
+/// Distances from the vertices and `samples` random surface points of `from`
+/// to the surface of `to`, infinite if `to` has no facets.
+pub fn one_sided(from: &Triangles, to: &Bvh, samples: usize, seed: u64) -> Deviation {
+    let distances: Vec<f64> = sample_surface(from, samples, seed)
+        .into_iter()
+        .map(|p| closest_point(to, p).map_or(f64::INFINITY, |c| c.distance as f64))
+        .collect();
+    Deviation::new(&distances)
+}
+
+/// Samples both meshes and measures the deviation in both directions.
+pub fn compare(a: &Triangles, b: &Triangles, samples: usize, seed: u64) -> Comparison {
+    Comparison {
+        forward: one_sided(a, &Bvh::new(b), samples, seed),
+        backward: one_sided(b, &Bvh::new(a), samples, seed),
+    }
+}
+
+/// Distance of every distinct vertex of `from` to the surface of `to`.
+pub fn vertex_deviations(from: &Triangles, to: &Bvh) -> Vec<(Vec3, Float)> {
+    IndexedMesh::from_triangles(from)
+        .vertices
+        .into_iter()
+        .map(|p| {
+            let distance = closest_point(to, p).map_or(Float::INFINITY, |c| c.distance);
+            (p, distance)
+        })
+        .collect()
+}
+
+/// Writes vertex deviations as `x,y,z,deviation` lines with a header.
+pub fn write_deviations(path: PathBuf, deviations: &[(Vec3, Float)]) -> anyhow::Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path.clone())
+        .with_context(|| {
+            format!(
+                "Failed to create a file for writing!\nPath: \'{}\'",
+                path.display()
+            )
+        })?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "x,y,z,deviation")?;
+    for (p, deviation) in deviations {
+        writeln!(writer, "{},{},{},{}", p.x, p.y, p.z, deviation)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    #[test]
+    fn test_sampling() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 2.0, 3.0).into());
+        let points = sample_surface(&triangles, 1000, 1);
+        assert_eq!(points.len(), 1008);
+        assert_eq!(points, sample_surface(&triangles, 1000, 1));
+        assert_ne!(points, sample_surface(&triangles, 1000, 2));
+
+        let bvh = Bvh::new(&triangles);
+        for p in &points {
+            assert!(closest_point(&bvh, *p).unwrap().distance < 1e-6);
+        }
+        // the largest faces get the most points
+        let on_big_faces = points[8..]
+            .iter()
+            .filter(|p| p.x == 0.0 || p.x == 1.0)
+            .count();
+        assert!(on_big_faces > 400);
+    }
+
+    #[test]
+    fn test_identical_and_offset() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let same = compare(&a, &a, 500, 7);
+        assert!(same.hausdorff() < 1e-6);
+        assert!(same.rms() < 1e-6);
+
+        // every point of the inner box is 0.1 away from the outer one
+        let b = cuboid((-0.1, -0.1, -0.1).into(), (1.1, 1.1, 1.1).into());
+        let grown = compare(&a, &b, 500, 7);
+        assert!((grown.forward.max - 0.1).abs() < 1e-6);
+        assert!((grown.forward.rms - 0.1).abs() < 1e-6);
+        assert!((grown.hausdorff() - 0.1 * 3.0f32.sqrt()).abs() < 1e-6);
+        assert!(grown.rms() > 0.1 && grown.rms() < grown.hausdorff());
+
+        for (_, deviation) in vertex_deviations(&a, &Bvh::new(&b)) {
+            assert!((deviation - 0.1).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_empty_mesh() {
+        let a = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let comparison = compare(&a, &Triangles::new(), 100, 1);
+        assert_eq!(comparison.forward.samples, 108);
+        assert_eq!(comparison.forward.max, Float::INFINITY);
+        assert_eq!(comparison.forward.mean, Float::INFINITY);
+        assert_eq!(comparison.backward.samples, 0);
+        assert_eq!(comparison.hausdorff(), Float::INFINITY);
+        assert_eq!(comparison.rms(), Float::INFINITY);
+
+        let nothing = compare(&Triangles::new(), &Triangles::new(), 100, 1);
+        assert_eq!(nothing.hausdorff(), 0.0);
+        assert!(nothing.forward.mean.is_sign_positive() && nothing.rms() == 0.0);
+    }
+
+    #[test]
+    fn test_one_sided_is_asymmetric() {
+        let small = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let large = cuboid((0.0, 0.0, 0.0).into(), (2.0, 2.0, 2.0).into());
+        let comparison = compare(&small, &large, 2000, 3);
+        assert!((comparison.forward.max - 1.0).abs() < 1e-6);
+        assert!((comparison.backward.max - 3.0f32.sqrt()).abs() < 1e-6);
+        assert_eq!(comparison.hausdorff(), comparison.backward.max);
+        assert_eq!(comparison.forward.samples, 2008);
+    }
+}
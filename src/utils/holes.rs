@@ -0,0 +1,245 @@
+use super::{
+    mesh::IndexedMesh,
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+    EPS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilledHole {
+    /// Number of boundary edges around the hole.
+    pub edges: usize,
+    /// Number of facets added to close it.
+    pub facets: usize,
+    pub area: Float,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HoleReport {
+    pub filled: Vec<FilledHole>,
+    /// Boundary loops left open because they exceed the size limit.
+    pub skipped: usize,
+}
+
+/// Closes every boundary loop of at most `max_hole_size` edges.
+///
+/// Each loop is projected onto its best-fit plane and triangulated by ear
+/// clipping. The new facets are wound to match the surrounding surface.
+pub fn fill_holes(triangles: &mut Triangles, max_hole_size: usize) -> HoleReport {
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let mut report = HoleReport::default();
+
+    for boundary in mesh.boundary_loops() {
+        if boundary.len() > max_hole_size {
+            report.skipped += 1;
+            continue;
+        }
+
+        // the adjacent faces walk the loop forwards, so the patch walks it backwards
+        let polygon: Vec<Vec3> = boundary.iter().rev().map(|&v| mesh.vertices[v]).collect();
+        let patch = triangulate_polygon(&polygon);
+
+        report.filled.push(FilledHole {
+            edges: boundary.len(),
+            facets: patch.len(),
+            area: patch.iter().map(|t| t.area()).sum(),
+        });
+        triangles.extend(patch);
+    }
+
+    report
+}
+
+/// Ear clipping of a (possibly non-planar) polygon on its best-fit plane.
+pub fn triangulate_polygon(polygon: &[Vec3]) -> Triangles {
+    let normal = newell_normal(polygon);
+    let normal = if normal.d3() < EPS {
+        Vec3::from((0.0, 0.0, 1.0))
+    } else {
+        normal.normalize()
+    };
+    let (u, v) = plane_basis(normal);
+    let points: Vec<(Float, Float)> = polygon.iter().map(|&p| (p.dot(u), p.dot(v))).collect();
+
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Triangles::new();
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n)
+            .find(|&i| is_ear(&points, &remaining, i))
+            // no proper ear means the projection is degenerate, clip the sharpest corner
+            .unwrap_or_else(|| {
+                (0..n)
+                    .max_by(|&a, &b| {
+                        corner(&points, &remaining, a).total_cmp(&corner(&points, &remaining, b))
+                    })
+                    .unwrap()
+            });
+
+        let prev = remaining[(ear + n - 1) % n];
+        let next = remaining[(ear + 1) % n];
+        triangles.push(Triangle::from_vertices([
+            polygon[prev],
+            polygon[remaining[ear]],
+            polygon[next],
+        ]));
+        remaining.remove(ear);
+    }
+
+    if remaining.len() == 3 {
+        triangles.push(Triangle::from_vertices(
+            [remaining[0], remaining[1], remaining[2]].map(|i| polygon[i]),
+        ));
+    }
+    triangles
+}
+
+/// Area-weighted normal of a polygon, robust for non-planar loops.
+pub fn newell_normal(polygon: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::from((0.0, 0.0, 0.0));
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        normal = normal
+            + Vec3::from((
+                (a.y - b.y) * (a.z + b.z),
+                (a.z - b.z) * (a.x + b.x),
+                (a.x - b.x) * (a.y + b.y),
+            ));
+    }
+    normal
+}
+
+/// Two unit vectors spanning the plane orthogonal to `normal`, right-handed with it.
+pub fn plane_basis(normal: Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.x.abs() < 0.9 {
+        Vec3::from((1.0, 0.0, 0.0))
+    } else {
+        Vec3::from((0.0, 1.0, 0.0))
+    };
+    let u = helper.cross(normal).normalize();
+    let v = normal.cross(u);
+    (u, v)
+}
+
+/// Twice the signed area of the corner at `remaining[i]`.
+fn corner(points: &[(Float, Float)], remaining: &[usize], i: usize) -> Float {
+    let n = remaining.len();
+    let a = points[remaining[(i + n - 1) % n]];
+    let b = points[remaining[i]];
+    let c = points[remaining[(i + 1) % n]];
+    cross_2d(a, b, c)
+}
+
+fn cross_2d(a: (Float, Float), b: (Float, Float), c: (Float, Float)) -> Float {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn is_ear(points: &[(Float, Float)], remaining: &[usize], i: usize) -> bool {
+    if corner(points, remaining, i) <= 0.0 {
+        return false;
+    }
+
+    let n = remaining.len();
+    let (ia, ib, ic) = (
+        remaining[(i + n - 1) % n],
+        remaining[i],
+        remaining[(i + 1) % n],
+    );
+    let (a, b, c) = (points[ia], points[ib], points[ic]);
+
+    remaining
+        .iter()
+        .filter(|&&j| j != ia && j != ib && j != ic)
+        .all(|&j| {
+            let p = points[j];
+            // points coinciding with the ear corners do not block it
+            if p == a || p == b || p == c {
+                return true;
+            }
+            !(cross_2d(a, b, p) >= 0.0 && cross_2d(b, c, p) >= 0.0 && cross_2d(c, a, p) >= 0.0)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mesh_report::MeshReport;
+
+    fn open_box() -> Triangles {
+        let p: Vec<Vec3> = [
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (0.0, 1.0, 0.0),
+            (0.0, 0.0, 1.0),
+            (1.0, 0.0, 1.0),
+            (1.0, 1.0, 1.0),
+            (0.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .map(Vec3::from)
+        .collect();
+        // every side except the top
+        [
+            [0, 2, 1],
+            [0, 3, 2],
+            [0, 1, 5],
+            [0, 5, 4],
+            [1, 2, 6],
+            [1, 6, 5],
+            [2, 3, 7],
+            [2, 7, 6],
+            [3, 0, 4],
+            [3, 4, 7],
+        ]
+        .iter()
+        .map(|f| Triangle::from_vertices(f.map(|i| p[i])))
+        .collect()
+    }
+
+    #[test]
+    fn test_fill_square_hole() {
+        let mut triangles = open_box();
+        let report = fill_holes(&mut triangles, 10);
+        assert_eq!(report.filled.len(), 1);
+        assert_eq!(report.filled[0].edges, 4);
+        assert_eq!(report.filled[0].facets, 2);
+        assert!((report.filled[0].area - 1.0).abs() < 1e-6);
+
+        let check = MeshReport::new(&triangles);
+        assert!(check.is_watertight());
+        assert!(check.is_manifold());
+        for t in &triangles[10..] {
+            assert!(t.normal.z > 0.99);
+        }
+    }
+
+    #[test]
+    fn test_hole_too_large() {
+        let mut triangles = open_box();
+        let report = fill_holes(&mut triangles, 3);
+        assert!(report.filled.is_empty());
+        assert_eq!(report.skipped, 1);
+        assert_eq!(triangles.len(), 10);
+    }
+
+    #[test]
+    fn test_triangulate_concave_polygon() {
+        let polygon: Vec<Vec3> = [
+            (0.0, 0.0, 0.0),
+            (2.0, 0.0, 0.0),
+            (2.0, 2.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (0.0, 2.0, 0.0),
+        ]
+        .into_iter()
+        .map(Vec3::from)
+        .collect();
+        let triangles = triangulate_polygon(&polygon);
+        assert_eq!(triangles.len(), 3);
+        let area: Float = triangles.iter().map(|t| t.area()).sum();
+        assert!((area - 3.0).abs() < 1e-5);
+        assert!(triangles.iter().all(|t| t.normal.z > 0.99));
+    }
+}
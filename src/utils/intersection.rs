@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    bounding_box::Aabb,
+    mesh::IndexedMesh,
+    point::{cross, dot, length, lerp, scale_by, sub, to_f64, to_vec3, Point},
+    stl_parser::{Triangle, Triangles},
+    vec3::Vec3,
+};
+
+/// A pair of facets whose interiors intersect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intersection {
+    pub facets: (usize, usize),
+    /// Segment shared by both facets, `None` for coplanar overlaps or when not requested.
+    pub segment: Option<(Vec3, Vec3)>,
+}
+
+/// Finds all pairs of intersecting facets.
+///
+/// Facets that share an edge are never reported; facets that share a single
+/// vertex are reported only if they cross beyond that vertex. Candidate pairs
+/// come from a uniform grid over the facet bounding boxes.
+pub fn self_intersections(triangles: &Triangles, with_segments: bool) -> Vec<Intersection> {
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let mut intersections = Vec::new();
+
+    for (i, j) in candidate_pairs(triangles) {
+        let shared = mesh.faces[i]
+            .iter()
+            .filter(|v| mesh.faces[j].contains(v))
+            .count();
+        if shared >= 2
+            || IndexedMesh::is_degenerate(&mesh.faces[i])
+            || IndexedMesh::is_degenerate(&mesh.faces[j])
+        {
+            continue;
+        }
+
+        if let Some(segment) = triangle_intersection(&triangles[i], &triangles[j]) {
+            intersections.push(Intersection {
+                facets: (i, j),
+                segment: if with_segments { segment } else { None },
+            });
+        }
+    }
+
+    intersections.sort_by_key(|intersection| intersection.facets);
+    intersections
+}
+
+/// Möller's triangle-triangle test.
+///
+/// Returns `None` when the triangles do not intersect, `Some(None)` for
+/// coplanar overlaps and `Some(Some(segment))` otherwise. Contacts of a single
+/// point are not considered intersections.
+pub fn triangle_intersection(a: &Triangle, b: &Triangle) -> Option<Option<(Vec3, Vec3)>> {
+    let a = a.vertices.map(to_f64);
+    let b = b.vertices.map(to_f64);
+
+    let scale = a
+        .iter()
+        .chain(b.iter())
+        .flat_map(|p| p.iter())
+        .fold(1.0f64, |acc, c| acc.max(c.abs()));
+    let eps = 1e-9 * scale;
+
+    let na = cross(sub(a[1], a[0]), sub(a[2], a[0]));
+    let nb = cross(sub(b[1], b[0]), sub(b[2], b[0]));
+    if length(na) < eps * eps || length(nb) < eps * eps {
+        return None;
+    }
+    let na = scale_by(na, 1.0 / length(na));
+    let nb = scale_by(nb, 1.0 / length(nb));
+
+    let da = a.map(|p| snap(dot(nb, sub(p, b[0])), eps));
+    let db = b.map(|p| snap(dot(na, sub(p, a[0])), eps));
+
+    if same_side(da) || same_side(db) {
+        return None;
+    }
+    if da.iter().all(|&d| d == 0.0) {
+        return coplanar_overlap(a, b, na).then_some(None);
+    }
+
+    let direction = cross(na, nb);
+    let (ia, pa) = plane_crossing(a, da, direction)?;
+    let (ib, pb) = plane_crossing(b, db, direction)?;
+
+    let (start, start_point) = if ia.0 > ib.0 {
+        (ia.0, pa.0)
+    } else {
+        (ib.0, pb.0)
+    };
+    let (end, end_point) = if ia.1 < ib.1 {
+        (ia.1, pa.1)
+    } else {
+        (ib.1, pb.1)
+    };
+
+    if end - start <= eps {
+        return None;
+    }
+    Some(Some((to_vec3(start_point), to_vec3(end_point))))
+}
+
+fn snap(d: f64, eps: f64) -> f64 {
+    if d.abs() < eps {
+        0.0
+    } else {
+        d
+    }
+}
+
+fn same_side(d: [f64; 3]) -> bool {
+    d.iter().all(|&d| d > 0.0) || d.iter().all(|&d| d < 0.0)
+}
+
+/// Interval along `direction` where the triangle crosses the other plane,
+/// together with the points at both ends.
+fn plane_crossing(
+    triangle: [Point; 3],
+    distances: [f64; 3],
+    direction: Point,
+) -> Option<((f64, f64), (Point, Point))> {
+    let mut points = Vec::with_capacity(3);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (di, dj) = (distances[i], distances[j]);
+        if di == 0.0 {
+            points.push(triangle[i]);
+        }
+        if di * dj < 0.0 {
+            let t = di / (di - dj);
+            points.push(lerp(triangle[i], triangle[j], t));
+        }
+    }
+
+    let first = *points.first()?;
+    let mut interval = (dot(direction, first), dot(direction, first));
+    let mut ends = (first, first);
+    for &p in &points[1..] {
+        let t = dot(direction, p);
+        if t < interval.0 {
+            interval.0 = t;
+            ends.0 = p;
+        }
+        if t > interval.1 {
+            interval.1 = t;
+            ends.1 = p;
+        }
+    }
+    Some((interval, ends))
+}
+
+/// Proper overlap of two coplanar triangles: crossing edges or a vertex strictly inside.
+fn coplanar_overlap(a: [Point; 3], b: [Point; 3], normal: Point) -> bool {
+    // drop the dominant axis of the normal
+    let axis = (0..3)
+        .max_by(|&i, &j| normal[i].abs().total_cmp(&normal[j].abs()))
+        .unwrap();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let a = a.map(|p| (p[u], p[v]));
+    let b = b.map(|p| (p[u], p[v]));
+
+    for i in 0..3 {
+        for j in 0..3 {
+            if segments_cross(a[i], a[(i + 1) % 3], b[j], b[(j + 1) % 3]) {
+                return true;
+            }
+        }
+    }
+    let centroid = |t: [(f64, f64); 3]| {
+        (
+            (t[0].0 + t[1].0 + t[2].0) / 3.0,
+            (t[0].1 + t[1].1 + t[2].1) / 3.0,
+        )
+    };
+    // the centroids catch identical triangles, which have no proper crossings
+    a.iter()
+        .chain([centroid(a)].iter())
+        .any(|&p| strictly_inside(p, b))
+        || b.iter()
+            .chain([centroid(b)].iter())
+            .any(|&p| strictly_inside(p, a))
+}
+
+fn orient_2d(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn segments_cross(p1: (f64, f64), p2: (f64, f64), q1: (f64, f64), q2: (f64, f64)) -> bool {
+    let d1 = orient_2d(q1, q2, p1);
+    let d2 = orient_2d(q1, q2, p2);
+    let d3 = orient_2d(p1, p2, q1);
+    let d4 = orient_2d(p1, p2, q2);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+fn strictly_inside(p: (f64, f64), triangle: [(f64, f64); 3]) -> bool {
+    let d = [
+        orient_2d(triangle[0], triangle[1], p),
+        orient_2d(triangle[1], triangle[2], p),
+        orient_2d(triangle[2], triangle[0], p),
+    ];
+    d.iter().all(|&d| d > 0.0) || d.iter().all(|&d| d < 0.0)
+}
+
+/// Facets whose bounding box covers more grid cells are tested against all
+/// other facets instead of being inserted into the grid.
+const MAX_CELLS_PER_FACET: u64 = 64;
+
+/// Pairs of facets whose bounding boxes overlap, found through a uniform grid.
+fn candidate_pairs(triangles: &Triangles) -> Vec<(usize, usize)> {
+    if triangles.is_empty() {
+        return Vec::new();
+    }
+
+    let bounds: Vec<Aabb> = triangles.iter().map(Aabb::from_triangle).collect();
+    let mut total = Aabb::empty();
+    let mut average_size = 0.0;
+    for aabb in &bounds {
+        total = total.union(aabb);
+        let size = aabb.size();
+        average_size += size.x.max(size.y).max(size.z) / triangles.len() as f32;
+    }
+
+    let extent = total.size();
+    let largest = extent.x.max(extent.y).max(extent.z);
+    // keep the grid at a sane size for very small facets
+    let cell = average_size.max(largest / 256.0).max(f32::MIN_POSITIVE);
+    let min = total.min;
+    let cell_of = |v: Vec3| {
+        (
+            ((v.x - min.x) / cell) as i32,
+            ((v.y - min.y) / cell) as i32,
+            ((v.z - min.z) / cell) as i32,
+        )
+    };
+
+    let mut grid: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::new();
+    let mut oversized = Vec::new();
+    for (i, aabb) in bounds.iter().enumerate() {
+        let (x0, y0, z0) = cell_of(aabb.min);
+        let (x1, y1, z1) = cell_of(aabb.max);
+        let cells = (x1 - x0 + 1) as u64 * (y1 - y0 + 1) as u64 * (z1 - z0 + 1) as u64;
+        if cells > MAX_CELLS_PER_FACET {
+            oversized.push(i);
+            continue;
+        }
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                for z in z0..=z1 {
+                    grid.entry((x, y, z)).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    let mut pairs = HashSet::new();
+    for facets in grid.values() {
+        for (k, &i) in facets.iter().enumerate() {
+            for &j in &facets[k + 1..] {
+                if bounds[i].intersects(&bounds[j]) {
+                    pairs.insert((i.min(j), i.max(j)));
+                }
+            }
+        }
+    }
+    for &i in &oversized {
+        for j in (0..bounds.len()).filter(|&j| j != i) {
+            if bounds[i].intersects(&bounds[j]) {
+                pairs.insert((i.min(j), i.max(j)));
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(points: [(f32, f32, f32); 3]) -> Triangle {
+        Triangle::from_vertices(points.map(Vec3::from))
+    }
+
+    #[test]
+    fn test_crossing_triangles() {
+        let a = triangle([(0.0, 0.0, 0.0), (2.0, 0.0, 0.0), (0.0, 2.0, 0.0)]);
+        let b = triangle([(0.5, 0.5, -1.0), (0.5, 0.5, 1.0), (3.0, 0.5, 0.0)]);
+        let (start, end) = triangle_intersection(&a, &b).unwrap().unwrap();
+        assert!((start.y - 0.5).abs() < 1e-6 && (end.y - 0.5).abs() < 1e-6);
+        assert!(start.z.abs() < 1e-6 && end.z.abs() < 1e-6);
+        assert!(((end - start).d3() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_separated_triangles() {
+        let a = triangle([(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)]);
+        let b = triangle([(0.0, 0.0, 1.0), (1.0, 0.0, 1.0), (0.0, 1.0, 2.0)]);
+        assert_eq!(triangle_intersection(&a, &b), None);
+    }
+
+    #[test]
+    fn test_coplanar_overlap() {
+        let a = triangle([(0.0, 0.0, 0.0), (2.0, 0.0, 0.0), (0.0, 2.0, 0.0)]);
+        let b = triangle([(0.5, 0.5, 0.0), (3.0, 0.5, 0.0), (0.5, 3.0, 0.0)]);
+        assert_eq!(triangle_intersection(&a, &b), Some(None));
+        assert_eq!(triangle_intersection(&a, &a), Some(None));
+    }
+
+    #[test]
+    fn test_self_intersections_skip_neighbours() {
+        let mut triangles = vec![
+            triangle([(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)]),
+            triangle([(1.0, 0.0, 0.0), (1.0, 1.0, 0.0), (0.0, 1.0, 0.0)]),
+            triangle([(0.0, 0.0, 0.0), (1.0, 0.0, 1.0), (0.0, 0.0, 1.0)]),
+        ];
+        assert!(self_intersections(&triangles, true).is_empty());
+
+        triangles.push(triangle([
+            (0.2, 0.2, -1.0),
+            (0.2, 0.2, 1.0),
+            (5.0, 0.2, 0.0),
+        ]));
+        let found = self_intersections(&triangles, true);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].facets, (0, 3));
+        assert_eq!(found[1].facets, (1, 3));
+        assert!(found.iter().all(|i| i.segment.is_some()));
+    }
+
+    #[test]
+    fn test_long_facet_among_small_ones() {
+        // small facets along the diagonal and one long facet through all of them
+        let mut triangles: Triangles = (0..100)
+            .map(|i| {
+                let o = i as f32 * 0.1;
+                triangle([
+                    (o - 0.01, o - 0.02, o),
+                    (o + 0.02, o - 0.01, o),
+                    (o - 0.01, o + 0.02, o),
+                ])
+            })
+            .collect();
+        triangles.push(triangle([
+            (-1.0, -1.0, -1.5),
+            (11.0, 11.0, 10.5),
+            (11.0, 11.0, 17.5),
+        ]));
+
+        let pairs = candidate_pairs(&triangles);
+        assert_eq!(pairs.len(), 100);
+        assert!(pairs.iter().all(|&(_, j)| j == 100));
+        assert_eq!(self_intersections(&triangles, false).len(), 100);
+    }
+}
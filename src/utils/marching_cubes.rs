@@ -0,0 +1,818 @@
+use std::collections::BTreeMap;
+
+use super::{
+    bounding_box::Aabb,
+    intersection::self_intersections,
+    point::{length, lerp, scale_by, sub, to_f64, to_vec3, Point},
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+/// Samples of a scalar field on a regular grid, x running fastest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarGrid {
+    /// Position of sample `[0, 0, 0]`.
+    pub origin: Vec3,
+    /// Distance between neighbouring samples along each axis.
+    pub spacing: Vec3,
+    pub dims: [usize; 3],
+    pub values: Vec<Float>,
+}
+
+impl ScalarGrid {
+    pub fn new(
+        origin: Vec3,
+        spacing: Vec3,
+        dims: [usize; 3],
+        values: Vec<Float>,
+    ) -> anyhow::Result<Self> {
+        if values.len() != dims[0] * dims[1] * dims[2] {
+            return Err(anyhow::anyhow!(
+                "Expected {} samples for a {} x {} x {} grid, got {}!",
+                dims[0] * dims[1] * dims[2],
+                dims[0],
+                dims[1],
+                dims[2],
+                values.len()
+            ));
+        }
+        Ok(ScalarGrid {
+            origin,
+            spacing,
+            dims,
+            values,
+        })
+    }
+
+    /// Samples `field` every `resolution` over `bounds` grown by one step, so
+    /// that surfaces inside `bounds` come out closed.
+    pub fn sample(field: impl Fn(Vec3) -> Float, bounds: &Aabb, resolution: Float) -> Self {
+        let step = Vec3::from((resolution, resolution, resolution));
+        let origin = bounds.min - step;
+        let size = bounds.size() + step * 2.0;
+        let dims = [size.x, size.y, size.z].map(|n| (n / resolution).ceil() as usize + 1);
+
+        let mut values = Vec::with_capacity(dims[0] * dims[1] * dims[2]);
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] {
+                    values.push(field(
+                        origin + Vec3::from((i as Float, j as Float, k as Float)) * resolution,
+                    ));
+                }
+            }
+        }
+        ScalarGrid {
+            origin,
+            spacing: step,
+            dims,
+            values,
+        }
+    }
+
+    pub fn value(&self, [i, j, k]: [usize; 3]) -> Float {
+        self.values[i + self.dims[0] * (j + self.dims[1] * k)]
+    }
+
+    pub fn position(&self, [i, j, k]: [usize; 3]) -> Vec3 {
+        self.origin + Vec3::from((i as Float, j as Float, k as Float)) * self.spacing
+    }
+}
+
+/// Cube corners as offsets, bit `a` of the index is the offset along axis `a`.
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [1, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [0, 1, 1],
+    [1, 1, 1],
+];
+
+/// Cube faces as corner loops, counter-clockwise seen from outside.
+const FACES: [[usize; 4]; 6] = [
+    [0, 4, 6, 2],
+    [1, 3, 7, 5],
+    [0, 1, 5, 4],
+    [2, 6, 7, 3],
+    [0, 2, 3, 1],
+    [4, 5, 7, 6],
+];
+
+/// Vertical cube edges, by their lower corner, in loop order around a
+/// horizontal slice of the cube.
+const COLUMNS: [usize; 4] = [0, 1, 3, 2];
+
+/// Triangulates the surface `value = iso` of the sampled field.
+///
+/// Samples below `iso` are inside, so the facets face towards increasing
+/// values, outwards for signed distance fields. The surface follows the
+/// topology of the trilinear interpolant in every cube, as the MC33 case table
+/// does, without the table:
+///
+/// - every cube face is cut into segments separately, ambiguous faces with
+///   alternating corners being resolved with the asymptotic decider, and the
+///   segments of a cube are chained into loops;
+/// - [`cube_regions`] finds which corners the inside and the outside connect
+///   through the interior of the cube. Two loops between the same inside and
+///   outside region bound one tunnel, which is stitched to a ring of points on
+///   the interpolated surface across its middle. Should those facets
+///   intersect each other, other rings are tried;
+/// - any other loop is closed by a fan around a point on the interpolated
+///   surface inside the cube, so that loops of up to 12 points that are far
+///   from planar do not fold over. Triangles and quadrilaterals are split
+///   directly, along a diagonal that does not lie in a cube face.
+///
+/// Since neighbouring cubes agree on their common face and share interpolated
+/// edge points, the result is watertight unless the surface leaves the grid.
+pub fn marching_cubes(grid: &ScalarGrid, iso: Float) -> Triangles {
+    let mut triangles = Triangles::new();
+    if grid.dims.iter().any(|&n| n < 2) {
+        return triangles;
+    }
+
+    for k in 0..grid.dims[2] - 1 {
+        for j in 0..grid.dims[1] - 1 {
+            for i in 0..grid.dims[0] - 1 {
+                let corner = |c: usize| {
+                    let d = CORNERS[c];
+                    [i + d[0], j + d[1], k + d[2]]
+                };
+                let values: [f64; 8] =
+                    std::array::from_fn(|c| (grid.value(corner(c)) - iso) as f64);
+                let inside = |c: usize| values[c] < 0.0;
+                if (0..8).all(inside) || !(0..8).any(inside) {
+                    continue;
+                }
+
+                // point where the surface crosses the cube edge (a, b),
+                // interpolated from the lower corner so that neighbouring
+                // cubes get identical points
+                let crossing = |(a, b): (usize, usize)| {
+                    let t = (values[a] / (values[a] - values[b])) as Float;
+                    let (p, q) = (grid.position(corner(a)), grid.position(corner(b)));
+                    p + (q - p) * t
+                };
+
+                let mut segments: BTreeMap<(usize, usize), (usize, usize)> = BTreeMap::new();
+                for face in FACES {
+                    // crossings in loop order; an entry edge runs from outside
+                    // to inside
+                    let edges: Vec<((usize, usize), bool)> = (0..4)
+                        .filter(|&n| inside(face[n]) != inside(face[(n + 1) % 4]))
+                        .map(|n| {
+                            let (a, b) = (face[n], face[(n + 1) % 4]);
+                            ((a.min(b), a.max(b)), inside(b))
+                        })
+                        .collect();
+
+                    let connected = edges.len() == 4 && inside_diagonal(face.map(|c| values[c]));
+                    let m = edges.len();
+                    for n in 0..m {
+                        let (edge, entry) = edges[n];
+                        if !entry {
+                            continue;
+                        }
+                        // the inside region lies to the right of the segment
+                        // seen from outside the cube
+                        let exit = if connected {
+                            edges[(n + m - 1) % m].0
+                        } else {
+                            edges[(n + 1) % m].0
+                        };
+                        segments.insert(edge, exit);
+                    }
+                }
+
+                let mut loops: Vec<Vec<(usize, usize)>> = Vec::new();
+                while let Some((&start, _)) = segments.first_key_value() {
+                    let mut edges = Vec::new();
+                    let mut edge = start;
+                    while let Some(next) = segments.remove(&edge) {
+                        edges.push(edge);
+                        edge = next;
+                    }
+                    loops.push(edges);
+                }
+
+                // loops between the same pair of regions bound one piece of
+                // the surface
+                let regions = cube_regions(&values);
+                let mut pieces: Vec<((usize, usize), Vec<usize>)> = Vec::new();
+                for (n, edges) in loops.iter().enumerate() {
+                    let (a, b) = edges[0];
+                    let key = if inside(a) {
+                        (regions[a], regions[b])
+                    } else {
+                        (regions[b], regions[a])
+                    };
+                    match pieces.iter_mut().find(|(other, _)| *other == key) {
+                        Some((_, members)) => members.push(n),
+                        None => pieces.push((key, vec![n])),
+                    }
+                }
+
+                // unit cube coordinates of crossings and back to positions
+                let local = |(a, b): (usize, usize)| -> Point {
+                    let t = values[a] / (values[a] - values[b]);
+                    lerp(
+                        CORNERS[a].map(|x| x as f64),
+                        CORNERS[b].map(|x| x as f64),
+                        t,
+                    )
+                };
+                let spacing = to_f64(grid.spacing);
+                let world = |u: Point| grid.position(corner(0)) + to_vec3(u) * grid.spacing;
+
+                // whether a facet faces towards increasing values of the
+                // interpolant
+                let facing = |[p, q, r]: [Vec3; 3]| {
+                    let center = to_f64((p + q + r) / 3.0 - grid.position(corner(0)));
+                    let (_, gradient) =
+                        trilinear(&values, std::array::from_fn(|i| center[i] / spacing[i]));
+                    let normal = to_f64((q - p).cross(r - p));
+                    (0..3)
+                        .map(|i| normal[i] * gradient[i] / spacing[i])
+                        .sum::<f64>()
+                        > 0.0
+                };
+                let mut push = |vertices: [Vec3; 3]| {
+                    if vertices[0] != vertices[1]
+                        && vertices[1] != vertices[2]
+                        && vertices[2] != vertices[0]
+                    {
+                        triangles.push(Triangle::from_vertices(vertices));
+                    }
+                };
+                for (_, members) in pieces {
+                    let vertices = |n: usize| -> Vec<(Option<(usize, usize)>, Vec3)> {
+                        loops[n]
+                            .iter()
+                            .map(|&edge| (Some(edge), crossing(edge)))
+                            .collect()
+                    };
+                    if let [a, b] = members[..] {
+                        // a tunnel, through the first ring whose facets do not
+                        // intersect each other, or straight from loop to loop
+                        let locals =
+                            |n: usize| loops[n].iter().map(|&edge| local(edge)).collect::<Vec<_>>();
+                        let clean = |strip: &Vec<[Vec3; 3]>| {
+                            let facets =
+                                strip.iter().map(|&t| Triangle::from_vertices(t)).collect();
+                            self_intersections(&facets, false).is_empty()
+                        };
+                        let through = |ring: Vec<Point>| {
+                            let ring: Vec<_> = ring.into_iter().map(|u| (None, world(u))).collect();
+                            let reversed: Vec<_> = ring.iter().rev().copied().collect();
+                            let mut strip = stitch(&vertices(a), &reversed, facing)?;
+                            strip.extend(stitch(&ring, &vertices(b), facing)?);
+                            Some(strip)
+                        };
+                        let (start, end) = (locals(a), locals(b));
+                        let mut strips = waists(&values, &start, &end).filter_map(through);
+                        let first = strips.next();
+                        let strip = first
+                            .clone()
+                            .filter(clean)
+                            .or_else(|| strips.find(clean))
+                            .or_else(|| stitch(&vertices(a), &vertices(b), facing).filter(clean))
+                            .or(first);
+                        if let Some(strip) = strip {
+                            strip.into_iter().for_each(&mut push);
+                            continue;
+                        }
+                    }
+                    for n in members {
+                        let polygon = vertices(n);
+                        let len = polygon.len();
+                        // split quadrilaterals along the shorter diagonal
+                        // that does not lie in a cube face
+                        let diagonal = (0..2)
+                            .filter(|&d| len == 4 && !share_face(polygon[d].0, polygon[d + 2].0))
+                            .min_by(|&d, &e| {
+                                let length = |d: usize| (polygon[d].1 - polygon[d + 2].1).d3();
+                                length(d).total_cmp(&length(e))
+                            });
+                        if len == 3 || diagonal.is_some() {
+                            let shift = diagonal.unwrap_or(0);
+                            for n in 1..len - 1 {
+                                push([
+                                    polygon[shift].1,
+                                    polygon[(n + shift) % len].1,
+                                    polygon[(n + 1 + shift) % len].1,
+                                ]);
+                            }
+                            continue;
+                        }
+                        let center = world(surface_point(
+                            &values,
+                            loops[n].iter().map(|&edge| local(edge)),
+                        ));
+                        for n in 0..len {
+                            push([center, polygon[n].1, polygon[(n + 1) % len].1]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    triangles
+}
+
+/// Whether the inside corners of a face with alternating corners, given in
+/// loop order, are connected across the face: the saddle of the bilinear
+/// interpolant is inside.
+fn inside_diagonal(v: [f64; 4]) -> bool {
+    (v[0] * v[2] - v[1] * v[3]) / (v[0] + v[2] - v[1] - v[3]) < 0.0
+}
+
+/// Connected regions of the inside and of the outside of the trilinear
+/// interpolant within the closed cube, as the representative corner of each
+/// corner.
+///
+/// Horizontal slices of the cube are bilinear, so their regions follow from
+/// the corner signs and the asymptotic decider. The slice topology only
+/// changes where a vertical edge crosses zero or where the slice saddle
+/// changes sign, so one slice per interval between those heights, plus the
+/// bottom and top face, sees every connection.
+fn cube_regions(values: &[f64; 8]) -> [usize; 8] {
+    fn find(parent: &mut [usize; 8], c: usize) -> usize {
+        let mut root = c;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[c] = root;
+        root
+    }
+
+    let (p, q) = (
+        COLUMNS.map(|c| values[c]),
+        COLUMNS.map(|c| values[c + 4] - values[c]),
+    );
+    let mut heights = vec![0.0, 1.0];
+    for n in 0..4 {
+        if (p[n] < 0.0) != (p[n] + q[n] < 0.0) {
+            heights.push(-p[n] / q[n]);
+        }
+    }
+    // the numerator of the slice saddle, v0 v2 - v1 v3, is quadratic in the height
+    let (a, b, c) = (
+        q[0] * q[2] - q[1] * q[3],
+        p[0] * q[2] + q[0] * p[2] - p[1] * q[3] - q[1] * p[3],
+        p[0] * p[2] - p[1] * p[3],
+    );
+    if a.abs() > f64::EPSILON * (b.abs() + c.abs()) {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            heights.extend([(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]);
+        }
+    } else if b != 0.0 {
+        heights.push(-c / b);
+    }
+    heights.retain(|t| (0.0..=1.0).contains(t));
+    heights.sort_by(f64::total_cmp);
+    let slices = heights
+        .windows(2)
+        .map(|w| (w[0] + w[1]) / 2.0)
+        .chain([0.0, 1.0]);
+
+    // vertical edges without a crossing connect their corners
+    let mut parent: [usize; 8] = std::array::from_fn(|c| c);
+    for c in COLUMNS {
+        if (values[c] < 0.0) == (values[c + 4] < 0.0) {
+            parent[c + 4] = c;
+        }
+    }
+    for t in slices {
+        // exact at both faces, to agree with the face segments
+        let v: [f64; 4] = std::array::from_fn(|n| p[n] * (1.0 - t) + (p[n] + q[n]) * t);
+        let below = |n: usize| v[n] < 0.0;
+        // the corner of the vertical edge that shares the sign of the slice corner
+        let node = |n: usize| {
+            let c = COLUMNS[n];
+            if below(n) == (values[c] < 0.0) {
+                c
+            } else {
+                c + 4
+            }
+        };
+        let mut links: Vec<(usize, usize)> = (0..4)
+            .map(|n| (n, (n + 1) % 4))
+            .filter(|&(m, n)| below(m) == below(n))
+            .collect();
+        if below(0) == below(2) && below(1) == below(3) && below(0) != below(1) {
+            links.push(if inside_diagonal(v) == below(0) {
+                (0, 2)
+            } else {
+                (1, 3)
+            });
+        }
+        for (m, n) in links {
+            let (a, b) = (find(&mut parent, node(m)), find(&mut parent, node(n)));
+            parent[a] = b;
+        }
+    }
+    std::array::from_fn(|c| find(&mut parent, c))
+}
+
+/// Point on the trilinear interpolated surface near the centroid of `points`,
+/// all in coordinates of the unit cube, kept off the cube faces.
+fn surface_point(values: &[f64; 8], points: impl Iterator<Item = Point>) -> Point {
+    let mut u = centroid(points);
+
+    // a few Newton steps along the gradient
+    for _ in 0..4 {
+        let (value, gradient) = trilinear(values, u);
+        let length2: f64 = gradient.iter().map(|g| g * g).sum();
+        if length2 <= f64::EPSILON * value.abs() {
+            break;
+        }
+        u = std::array::from_fn(|axis| {
+            (u[axis] - value * gradient[axis] / length2).clamp(0.05, 0.95)
+        });
+    }
+    u
+}
+
+/// Value and gradient of the trilinear interpolant at `u` in coordinates of
+/// the unit cube.
+fn trilinear(values: &[f64; 8], u: Point) -> (f64, Point) {
+    let (mut value, mut gradient) = (0.0, [0.0; 3]);
+    for (c, &v) in values.iter().enumerate() {
+        let weight = |axis: usize| {
+            if CORNERS[c][axis] == 1 {
+                u[axis]
+            } else {
+                1.0 - u[axis]
+            }
+        };
+        let slope = |axis: usize| if CORNERS[c][axis] == 1 { v } else { -v };
+        value += v * weight(0) * weight(1) * weight(2);
+        gradient[0] += slope(0) * weight(1) * weight(2);
+        gradient[1] += weight(0) * slope(1) * weight(2);
+        gradient[2] += weight(0) * weight(1) * slope(2);
+    }
+    (value, gradient)
+}
+
+/// Whether points on the cube edges `e` and `f` lie in a common cube face,
+/// never for points inside the cube.
+fn share_face(e: Option<(usize, usize)>, f: Option<(usize, usize)>) -> bool {
+    let (Some(e), Some(f)) = (e, f) else {
+        return false;
+    };
+    let (all, any) = (e.0 & e.1 & f.0 & f.1, e.0 | e.1 | f.0 | f.1);
+    all != 0 || any != 7
+}
+
+fn centroid(points: impl Iterator<Item = Point>) -> Point {
+    let (sum, count) = points.fold(([0.0; 3], 0.0), |(sum, count), p| {
+        (std::array::from_fn(|i| sum[i] + p[i]), count + 1.0)
+    });
+    scale_by(sum, 1.0 / count)
+}
+
+/// Rings of points on the trilinear interpolated surface across the tunnel
+/// between the loops `a` and `b`, in coordinates of the unit cube and winding
+/// like `a`. Points at the same fraction of the length of `a` and of `b`
+/// walked backwards are paired up, and a point part of the way from one to
+/// the other is moved onto the surface. The rings start halfway along the
+/// tunnel with the offset that keeps the pairs closest.
+fn waists<'a>(
+    values: &'a [f64; 8],
+    a: &'a [Point],
+    b: &[Point],
+) -> impl Iterator<Item = Vec<Point>> + 'a {
+    // point at fraction `t` of the length of a closed polyline
+    fn along(points: &[Point], t: f64) -> Point {
+        let edge = |n: usize| (points[n], points[(n + 1) % points.len()]);
+        let total: f64 = (0..points.len())
+            .map(|n| length(sub(edge(n).1, edge(n).0)))
+            .sum();
+        let mut rest = t.rem_euclid(1.0) * total;
+        for n in 0..points.len() {
+            let (p, q) = edge(n);
+            let side = length(sub(q, p));
+            if rest <= side {
+                return lerp(p, q, if side > 0.0 { rest / side } else { 0.0 });
+            }
+            rest -= side;
+        }
+        points[0]
+    }
+
+    let b: Vec<Point> = b.iter().rev().copied().collect();
+    let count = a.len() + b.len();
+    let pairs = move |offset: f64| -> Vec<(Point, Point)> {
+        (0..count)
+            .map(|n| {
+                let t = n as f64 / count as f64;
+                (along(a, t), along(&b, t + offset))
+            })
+            .collect()
+    };
+    let spread = |offset: f64| {
+        pairs(offset)
+            .iter()
+            .map(|&(p, q)| length(sub(p, q)))
+            .sum::<f64>()
+    };
+    let mut offsets: Vec<f64> = (0..4 * count)
+        .map(|n| n as f64 / (4 * count) as f64)
+        .collect();
+    offsets.sort_by(|&x, &y| spread(x).total_cmp(&spread(y)));
+
+    [0.5, 0.3, 0.7]
+        .into_iter()
+        .flat_map(move |part| {
+            offsets
+                .clone()
+                .into_iter()
+                .map(move |offset| (part, offset))
+        })
+        .map(move |(part, offset)| {
+            pairs(offset)
+                .into_iter()
+                .map(|(p, q)| surface_point(values, std::iter::once(lerp(p, q, part))))
+                .collect()
+        })
+}
+
+/// Closes the tunnel between two loops of crossing edges and points with a
+/// strip of facets. No diagonal may lie in a cube face, where it could overlap
+/// the neighbouring cube. Among the other strips this one has the fewest
+/// facets that are not `facing` the right way, then the shortest diagonals.
+/// Both loops run the way the surface is oriented, so one is walked backwards.
+/// Returns `None` if there is no such strip.
+fn stitch(
+    a: &[(Option<(usize, usize)>, Vec3)],
+    b: &[(Option<(usize, usize)>, Vec3)],
+    facing: impl Fn([Vec3; 3]) -> bool,
+) -> Option<Vec<[Vec3; 3]>> {
+    type Cost = (usize, Float);
+    const UNREACHABLE: Cost = (usize::MAX, Float::INFINITY);
+    let order = |x: &Cost, y: &Cost| x.0.cmp(&y.0).then(x.1.total_cmp(&y.1));
+    let (m, n) = (a.len(), b.len());
+
+    // strip from diagonal (0, 0) to (m, n) through diagonals (i, j) between
+    // a[i] and b[start - j]. Sweeping a whole loop from one point of the other
+    // would use a diagonal twice, so besides the best strip to each diagonal,
+    // ANY, keep the best one that did not reach it straight along a from
+    // i = 0, ROW, or along b from j = 0, COLUMN
+    const ANY: usize = 0;
+    const ROW: usize = 1;
+    const COLUMN: usize = 2;
+    let previous = |t: usize, i: usize, j: usize| {
+        let along_a = (i > 0).then(|| {
+            let from = match t {
+                _ if i == m => ROW,
+                ROW => ROW,
+                _ => ANY,
+            };
+            (from, i - 1, j, true)
+        });
+        let along_b = (j > 0).then(|| {
+            let from = match t {
+                _ if j == n => COLUMN,
+                COLUMN => COLUMN,
+                _ => ANY,
+            };
+            (from, i, j - 1, false)
+        });
+        [along_a, along_b].into_iter().flatten()
+    };
+
+    let mut best: Option<(Cost, Vec<[Vec3; 3]>)> = None;
+    for start in 0..n {
+        let point_b = |j: usize| b[(start + n - j) % n];
+        let diagonal = |i: usize, j: usize| {
+            let ((e, p), (f, q)) = (a[i % m], point_b(j));
+            (!share_face(e, f)).then(|| (p - q).d3())
+        };
+        // facet of the step to diagonal (i, j)
+        let triangle = |i: usize, j: usize, along_a: bool| {
+            if along_a {
+                [a[i - 1].1, a[i % m].1, point_b(j).1]
+            } else {
+                [point_b(j).1, point_b(j - 1).1, a[i % m].1]
+            }
+        };
+
+        let mut cost = vec![vec![[UNREACHABLE; 3]; n + 1]; m + 1];
+        let step_cost =
+            |cost: &[Vec<[Cost; 3]>], i: usize, j: usize, step: (usize, usize, usize, bool)| {
+                let (t, before_i, before_j, along_a) = step;
+                let (backwards, length) = cost[before_i][before_j][t];
+                let forward = facing(triangle(i, j, along_a));
+                (backwards.saturating_add(!forward as usize), length)
+            };
+        for i in 0..=m {
+            for j in 0..=n {
+                let Some(length) = diagonal(i, j) else {
+                    continue;
+                };
+                for t in [ANY, ROW, COLUMN] {
+                    cost[i][j][t] = match (i, j, t) {
+                        (0, 0, ANY) => (0, length),
+                        (0, _, ROW) | (_, 0, COLUMN) => UNREACHABLE,
+                        _ => previous(t, i, j)
+                            .map(|step| step_cost(&cost, i, j, step))
+                            .min_by(order)
+                            .map_or(UNREACHABLE, |(backwards, before)| {
+                                (backwards, before + length)
+                            }),
+                    };
+                }
+            }
+        }
+        let total = cost[m][n][ANY];
+        if total.0 == usize::MAX || best.as_ref().is_some_and(|(c, _)| order(&total, c).is_ge()) {
+            continue;
+        }
+
+        let (mut t, mut i, mut j, mut strip) = (ANY, m, n, Vec::with_capacity(m + n));
+        while i + j > 0 {
+            let step = previous(t, i, j)
+                .min_by(|&x, &y| order(&step_cost(&cost, i, j, x), &step_cost(&cost, i, j, y)))
+                .unwrap();
+            strip.push(triangle(i, j, step.3));
+            (t, i, j) = (step.0, step.1, step.2);
+        }
+        best = Some((total, strip));
+    }
+    best.map(|(_, strip)| strip)
+}
+
+/// Samples `field` over `bounds` every `resolution` and triangulates
+/// `field = iso`, see [`ScalarGrid::sample`] and [`marching_cubes`].
+pub fn marching_cubes_fn(
+    field: impl Fn(Vec3) -> Float,
+    bounds: &Aabb,
+    resolution: Float,
+    iso: Float,
+) -> Triangles {
+    marching_cubes(&ScalarGrid::sample(field, bounds, resolution), iso)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        intersection::self_intersections, mass_properties::MassProperties, mesh_report::MeshReport,
+    };
+
+    fn bounds(half: Float) -> Aabb {
+        Aabb {
+            min: Vec3::from((-half, -half, -half)),
+            max: Vec3::from((half, half, half)),
+        }
+    }
+
+    #[test]
+    fn test_sphere() {
+        let sphere = |p: Vec3| p.d3() - 1.0;
+        let triangles = marching_cubes_fn(sphere, &bounds(1.0), 0.1, 0.0);
+
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!(report.genus, Some(0));
+        let volume = MassProperties::new(&triangles, 1.0).volume;
+        let expected = 4.0 / 3.0 * std::f64::consts::PI;
+        assert!((volume - expected).abs() / expected < 0.02);
+        // slivers next to samples on the surface have no usable normal
+        for triangle in triangles.iter().filter(|t| t.area() > 1e-6) {
+            let center = (triangle.vertices[0] + triangle.vertices[1] + triangle.vertices[2]) / 3.0;
+            assert!(triangle.normal.dot(center) > 0.0);
+        }
+        assert!(self_intersections(&triangles, false).is_empty());
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = |p: Vec3| {
+            let ring = (p.x * p.x + p.y * p.y).sqrt() - 1.0;
+            (ring * ring + p.z * p.z).sqrt() - 0.35
+        };
+        let triangles = marching_cubes_fn(torus, &bounds(1.4), 0.07, 0.0);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!(report.genus, Some(1));
+        assert!(self_intersections(&triangles, false).is_empty());
+    }
+
+    #[test]
+    fn test_single_corner() {
+        let mut values = vec![1.0; 8];
+        values[0] = -1.0;
+        let grid = ScalarGrid::new(
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 1.0, 1.0).into(),
+            [2, 2, 2],
+            values.clone(),
+        )
+        .unwrap();
+        let triangles = marching_cubes(&grid, 0.0);
+        assert_eq!(triangles.len(), 1);
+        assert!((triangles[0].normal - Vec3::from((1.0, 1.0, 1.0)).normalize()).d3() < 1e-6);
+        assert!(triangles[0].vertices.contains(&Vec3::from((0.5, 0.0, 0.0))));
+
+        values.pop();
+        assert!(ScalarGrid::new(
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 1.0, 1.0).into(),
+            [2, 2, 2],
+            values
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_random_field_is_closed() {
+        let mut seed = 99u32;
+        let mut random = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 - 0.5
+        };
+        let n = 8;
+        let values: Vec<Float> = (0..n * n * n)
+            .map(|index| {
+                let c = [index % n, index / n % n, index / (n * n)];
+                // positive border keeps the surface inside the grid
+                if c.iter().any(|&x| x == 0 || x == n - 1) {
+                    1.0
+                } else {
+                    random()
+                }
+            })
+            .collect();
+        let grid = ScalarGrid::new(
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 1.0, 1.0).into(),
+            [n; 3],
+            values,
+        )
+        .unwrap();
+        let triangles = marching_cubes(&grid, 0.0);
+        assert!(!triangles.is_empty());
+        assert!(MeshReport::new(&triangles).is_watertight());
+        assert!(MassProperties::new(&triangles, 1.0).volume > 0.0);
+        assert!(self_intersections(&triangles, false).is_empty());
+    }
+
+    /// 4 x 4 x 4 grid whose middle cube has `corner` at two opposite corners
+    /// and `middle` at the others.
+    fn saddle_grid(border: Float, middle: Float, corner: Float) -> ScalarGrid {
+        let values = (0..64)
+            .map(|index| {
+                let c = [index % 4, index / 4 % 4, index / 16];
+                if c.iter().any(|&x| x == 0 || x == 3) {
+                    border
+                } else if c == [1, 1, 1] || c == [2, 2, 2] {
+                    corner
+                } else {
+                    middle
+                }
+            })
+            .collect();
+        ScalarGrid::new(
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 1.0, 1.0).into(),
+            [4; 3],
+            values,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_interior_saddle() {
+        // the inside corners meet through the middle of the cube
+        let triangles = marching_cubes(&saddle_grid(0.2, 0.2, -1.0), 0.0);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!((report.components, report.genus), (1, Some(0)));
+        assert!(self_intersections(&triangles, false).is_empty());
+
+        let triangles = marching_cubes(&saddle_grid(0.5, 0.5, -1.0), 0.0);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!(report.components, 2);
+
+        // a ring of inside corners around an outside tunnel
+        let triangles = marching_cubes(&saddle_grid(1.0, -0.2, 1.0), 0.0);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!((report.components, report.genus), (1, Some(1)));
+        assert!(MassProperties::new(&triangles, 1.0).volume > 0.0);
+        assert!(self_intersections(&triangles, false).is_empty());
+
+        let triangles = marching_cubes(&saddle_grid(1.0, -0.5, 1.0), 0.0);
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!((report.components, report.genus), (1, Some(0)));
+    }
+}
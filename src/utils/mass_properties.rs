@@ -0,0 +1,157 @@
+use std::fmt;
+
+use super::{
+    matrix::Mat3,
+    point::{cross, dot, length, sub, to_f64, to_vec3},
+    stl_parser::Triangles,
+    vec3::Vec3,
+};
+
+/// Integral properties of a closed, outward oriented mesh of uniform density.
+///
+/// Everything is accumulated in `f64`; the volume integrals follow from the
+/// divergence theorem by summing signed tetrahedra spanned with the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+    pub volume: f64,
+    pub surface_area: f64,
+    pub density: f64,
+    pub mass: f64,
+    /// Centroid of the surface.
+    pub area_centroid: Vec3,
+    /// Centre of mass of the enclosed solid.
+    pub volume_centroid: Vec3,
+    /// Inertia tensor about the centre of mass.
+    pub inertia: Mat3,
+    /// Principal moments of inertia in ascending order.
+    pub principal_moments: [f64; 3],
+    /// Unit principal axes matching `principal_moments`.
+    pub principal_axes: [Vec3; 3],
+}
+
+impl MassProperties {
+    pub fn new(triangles: &Triangles, density: f64) -> Self {
+        let mut volume = 0.0;
+        let mut surface_area = 0.0;
+        let mut area_moment = [0.0; 3];
+        let mut volume_moment = [0.0; 3];
+        // second moments about the origin: integrals of x_i * x_j over the solid
+        let mut second = [[0.0; 3]; 3];
+
+        for triangle in triangles {
+            let [a, b, c] = triangle.vertices.map(to_f64);
+
+            let v = dot(a, cross(b, c)) / 6.0;
+            volume += v;
+
+            let area = length(cross(sub(b, a), sub(c, a))) / 2.0;
+            surface_area += area;
+
+            for i in 0..3 {
+                let sum = a[i] + b[i] + c[i];
+                area_moment[i] += area * sum / 3.0;
+                // the fourth vertex of the tetrahedron is the origin
+                volume_moment[i] += v * sum / 4.0;
+                for j in 0..3 {
+                    let products = a[i] * a[j] + b[i] * b[j] + c[i] * c[j];
+                    let sums = sum * (a[j] + b[j] + c[j]);
+                    second[i][j] += v * (products + sums) / 20.0;
+                }
+            }
+        }
+
+        let area_centroid = if surface_area > 0.0 {
+            area_moment.map(|m| m / surface_area)
+        } else {
+            [0.0; 3]
+        };
+        let volume_centroid = if volume != 0.0 {
+            volume_moment.map(|m| m / volume)
+        } else {
+            area_centroid
+        };
+
+        let mass = density * volume;
+        let mut inertia = Mat3::default();
+        let trace = second[0][0] + second[1][1] + second[2][2];
+        let c = volume_centroid;
+        let c2 = dot(c, c);
+        for i in 0..3 {
+            for j in 0..3 {
+                let kronecker = if i == j { 1.0 } else { 0.0 };
+                let about_origin = density * (kronecker * trace - second[i][j]);
+                // parallel axis theorem
+                inertia.m[i][j] = about_origin - mass * (kronecker * c2 - c[i] * c[j]);
+            }
+        }
+
+        let (principal_moments, axes) = inertia.symmetric_eigen();
+
+        MassProperties {
+            volume,
+            surface_area,
+            density,
+            mass,
+            area_centroid: to_vec3(area_centroid),
+            volume_centroid: to_vec3(volume_centroid),
+            inertia,
+            principal_moments,
+            principal_axes: axes.map(to_vec3),
+        }
+    }
+}
+
+impl fmt::Display for MassProperties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let point = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        writeln!(f, "volume:            {}", self.volume)?;
+        writeln!(f, "surface area:      {}", self.surface_area)?;
+        writeln!(f, "density:           {}", self.density)?;
+        writeln!(f, "mass:              {}", self.mass)?;
+        writeln!(f, "area centroid:     {}", point(self.area_centroid))?;
+        writeln!(f, "volume centroid:   {}", point(self.volume_centroid))?;
+        writeln!(f, "inertia tensor:")?;
+        for row in self.inertia.m {
+            writeln!(f, "  {} {} {}", row[0], row[1], row[2])?;
+        }
+        writeln!(f, "principal moments / axes:")?;
+        for (moment, axis) in self.principal_moments.iter().zip(self.principal_axes) {
+            writeln!(f, "  {}: {}", moment, point(axis))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    #[test]
+    fn test_box_properties() {
+        let (a, b, c) = (2.0, 3.0, 4.0);
+        let triangles = cuboid((1.0, 1.0, 1.0).into(), (3.0, 4.0, 5.0).into());
+        let props = MassProperties::new(&triangles, 2.0);
+
+        assert!((props.volume - a * b * c).abs() < 1e-9);
+        assert!((props.surface_area - 2.0 * (a * b + b * c + a * c)).abs() < 1e-9);
+        assert!((props.mass - 2.0 * a * b * c).abs() < 1e-9);
+        assert_eq!(props.volume_centroid, Vec3::from((2.0, 2.5, 3.0)));
+        assert_eq!(props.area_centroid, Vec3::from((2.0, 2.5, 3.0)));
+
+        let m = props.mass;
+        let expected = [
+            m * (b * b + c * c) / 12.0,
+            m * (a * a + c * c) / 12.0,
+            m * (a * a + b * b) / 12.0,
+        ];
+        for (i, (row, expected)) in props.inertia.m.iter().zip(expected).enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                let expected = if i == j { expected } else { 0.0 };
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+        assert!((props.principal_moments[0] - expected[2]).abs() < 1e-9);
+        assert!((props.principal_axes[0].z.abs() - 1.0).abs() < 1e-6);
+    }
+}
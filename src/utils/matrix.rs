@@ -0,0 +1,210 @@
+use std::ops;
+
+use super::vec3::{Float, Vec3};
+
+/// Affine transformation in homogeneous coordinates, stored row-major.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[Float; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Mat4 { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut matrix = Mat4::identity();
+        matrix.m[0][3] = offset.x;
+        matrix.m[1][3] = offset.y;
+        matrix.m[2][3] = offset.z;
+        matrix
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut matrix = Mat4::identity();
+        matrix.m[0][0] = factors.x;
+        matrix.m[1][1] = factors.y;
+        matrix.m[2][2] = factors.z;
+        matrix
+    }
+
+    /// Rotation by `angle` radians around the unit vector `axis` (right-hand rule).
+    pub fn rotation(axis: Vec3, angle: Float) -> Self {
+        let (s, c) = angle.sin_cos();
+        let t = 1.0 - c;
+        let Vec3 { x, y, z } = axis;
+        let mut matrix = Mat4::identity();
+        matrix.m[0][..3].copy_from_slice(&[t * x * x + c, t * x * y - s * z, t * x * z + s * y]);
+        matrix.m[1][..3].copy_from_slice(&[t * x * y + s * z, t * y * y + c, t * y * z - s * x]);
+        matrix.m[2][..3].copy_from_slice(&[t * x * z - s * y, t * y * z + s * x, t * z * z + c]);
+        matrix
+    }
+
+    /// Rotation by the Euler angles in degrees, applied around x, then y, then z.
+    pub fn rotation_degrees(angles: Vec3) -> Self {
+        Mat4::rotation((0.0, 0.0, 1.0).into(), angles.z.to_radians())
+            * Mat4::rotation((0.0, 1.0, 0.0).into(), angles.y.to_radians())
+            * Mat4::rotation((1.0, 0.0, 0.0).into(), angles.x.to_radians())
+    }
+
+    /// Reflection through the plane containing the origin with the given normal.
+    pub fn mirror(normal: Vec3) -> Self {
+        let n = normal.normalize();
+        let mut matrix = Mat4::identity();
+        for (i, a) in [n.x, n.y, n.z].into_iter().enumerate() {
+            for (j, b) in [n.x, n.y, n.z].into_iter().enumerate() {
+                matrix.m[i][j] -= 2.0 * a * b;
+            }
+        }
+        matrix
+    }
+
+    /// Determinant of the linear part; negative for mirroring transforms.
+    pub fn determinant(&self) -> Float {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Transforms a surface normal with the cofactor matrix of the linear part,
+    /// which is the inverse transpose scaled by the determinant.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.m;
+        let cofactor = |i: usize, j: usize| {
+            let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+            let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        Vec3::from((
+            cofactor(0, 0) * n.x + cofactor(0, 1) * n.y + cofactor(0, 2) * n.z,
+            cofactor(1, 0) * n.x + cofactor(1, 1) * n.y + cofactor(1, 2) * n.z,
+            cofactor(2, 0) * n.x + cofactor(2, 1) * n.y + cofactor(2, 2) * n.z,
+        ))
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        self.transform_vector(p) + Vec3::from((self.m[0][3], self.m[1][3], self.m[2][3]))
+    }
+
+    /// Applies only the linear part, ignoring the translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::from((
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        ))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::identity()
+    }
+}
+
+impl ops::Mul for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Mat4) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+/// Symmetric 3x3 matrix in double precision, used for inertia and covariance tensors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Mat3 {
+    pub m: [[f64; 3]; 3],
+}
+
+impl Mat3 {
+    /// Eigenvalues in ascending order and the matching unit eigenvectors, by Jacobi rotations.
+    pub fn symmetric_eigen(&self) -> ([f64; 3], [[f64; 3]; 3]) {
+        let mut a = self.m;
+        let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+        for _ in 0..64 {
+            let (p, q) = [(0, 1), (0, 2), (1, 2)]
+                .into_iter()
+                .max_by(|&(i, j), &(k, l)| a[i][j].abs().total_cmp(&a[k][l].abs()))
+                .unwrap();
+            let scale = a[0][0].abs() + a[1][1].abs() + a[2][2].abs();
+            if a[p][q].abs() <= 1e-15 * scale.max(f64::MIN_POSITIVE) {
+                break;
+            }
+
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let t = if theta == 0.0 { 1.0 } else { t };
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            for row in a.iter_mut() {
+                let (x, y) = (row[p], row[q]);
+                row[p] = c * x - s * y;
+                row[q] = s * x + c * y;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+            for row in v.iter_mut() {
+                let (x, y) = (row[p], row[q]);
+                row[p] = c * x - s * y;
+                row[q] = s * x + c * y;
+            }
+        }
+
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| a[i][i].total_cmp(&a[j][j]));
+        let values = order.map(|i| a[i][i]);
+        let vectors = order.map(|i| [v[0][i], v[1][i], v[2][i]]);
+        (values, vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_point() {
+        let matrix =
+            Mat4::translation((1.0, 2.0, 3.0).into()) * Mat4::scaling((2.0, 2.0, 2.0).into());
+        assert_eq!(
+            matrix.transform_point((1.0, 1.0, 1.0).into()),
+            Vec3::from((3.0, 4.0, 5.0))
+        );
+        assert_eq!(
+            matrix.transform_vector((1.0, 1.0, 1.0).into()),
+            Vec3::from((2.0, 2.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let matrix = Mat3 {
+            m: [[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 5.0]],
+        };
+        let (values, vectors) = matrix.symmetric_eigen();
+        for (value, expected) in values.iter().zip([1.0, 3.0, 5.0]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        for (value, vector) in values.iter().zip(vectors) {
+            for i in 0..3 {
+                let product: f64 = (0..3).map(|j| matrix.m[i][j] * vector[j]).sum();
+                assert!((product - value * vector[i]).abs() < 1e-12);
+            }
+        }
+    }
+}
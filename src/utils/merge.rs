@@ -0,0 +1,83 @@
+use super::{
+    matrix::Mat4,
+    mesh::IndexedMesh,
+    stl_parser::{Triangle, Triangles},
+    transform::transform,
+    vec3::Float,
+};
+
+/// Concatenates meshes, applying the optional transform of each one first.
+///
+/// With `weld_tolerance`, boundary vertices of the result that lie within the
+/// tolerance of each other are snapped together so touching open borders
+/// become shared edges. Facets that collapse while welding are dropped.
+pub fn merge(meshes: Vec<(Triangles, Option<Mat4>)>, weld_tolerance: Option<Float>) -> Triangles {
+    let mut merged = Triangles::new();
+    for (triangles, matrix) in meshes {
+        let mut triangles = triangles;
+        if let Some(matrix) = matrix {
+            transform(&mut triangles, &matrix);
+        }
+        merged.extend(triangles);
+    }
+
+    match weld_tolerance {
+        Some(tolerance) => weld_boundaries(merged, tolerance),
+        None => merged,
+    }
+}
+
+fn weld_boundaries(triangles: Triangles, tolerance: Float) -> Triangles {
+    let mut mesh = IndexedMesh::from_triangles(&triangles);
+    let mut boundary: Vec<usize> = mesh
+        .edge_faces()
+        .into_iter()
+        .filter(|(_, faces)| faces.len() == 1)
+        .flat_map(|((a, b), _)| [a, b])
+        .collect();
+    boundary.sort_unstable();
+    boundary.dedup();
+
+    mesh.snap_vertices(&boundary, tolerance);
+
+    mesh.faces
+        .iter()
+        .zip(triangles)
+        .filter(|(face, _)| !IndexedMesh::is_degenerate(face))
+        .map(|(face, triangle)| {
+            if face.iter().any(|v| boundary.binary_search(v).is_ok()) {
+                Triangle::from_vertices(face.map(|v| mesh.vertices[v]))
+            } else {
+                triangle
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{mesh_report::MeshReport, vec3::Vec3};
+
+    #[test]
+    fn test_merge_welds_touching_borders() {
+        let left = vec![Triangle::from_vertices([
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 0.0, 0.0).into(),
+            (0.0, 1.0, 0.0).into(),
+        ])];
+        let right = vec![Triangle::from_vertices([
+            (1.0, 0.0, 0.0).into(),
+            (1.0, 1.0, 0.0).into(),
+            (0.0, 1.0, 0.0).into(),
+        ])];
+        let shift = Some(Mat4::translation(Vec3::from((1e-4, 0.0, 0.0))));
+
+        let merged = merge(vec![(left.clone(), None), (right.clone(), shift)], None);
+        assert_eq!(MeshReport::new(&merged).boundary_edges, 6);
+
+        let merged = merge(vec![(left, None), (right, shift)], Some(1e-3));
+        assert_eq!(merged.len(), 2);
+        assert_eq!(MeshReport::new(&merged).boundary_edges, 4);
+    }
+}
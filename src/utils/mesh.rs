@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use super::{
+    stl_parser::{Triangle, Triangles},
+    vec3::{Float, Vec3},
+    EPS,
+};
+
+/// Undirected edge between two vertex indices, stored as `(min, max)`.
+pub type Edge = (usize, usize);
+
+pub fn edge(a: usize, b: usize) -> Edge {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Triangle mesh with shared vertices.
+///
+/// Face `i` always corresponds to triangle `i` of the soup it was built from,
+/// so per-face results can be mapped back onto the original `Triangles`.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    /// Welds vertices that are closer than `EPS`.
+    pub fn from_triangles(triangles: &Triangles) -> Self {
+        IndexedMesh::weld(triangles, EPS)
+    }
+
+    /// Welds vertices that are closer than `tolerance`.
+    pub fn weld(triangles: &Triangles, tolerance: Float) -> Self {
+        let mut welder = VertexWelder::new(tolerance);
+        let faces = triangles
+            .iter()
+            .map(|triangle| triangle.vertices.map(|vertex| welder.insert(vertex)))
+            .collect();
+
+        IndexedMesh {
+            vertices: welder.vertices,
+            faces,
+        }
+    }
+
+    /// Redirects every vertex in `subset` to the first vertex of the subset within
+    /// `tolerance`. Faces can become degenerate in the process.
+    pub fn snap_vertices(&mut self, subset: &[usize], tolerance: Float) {
+        let mut welder = VertexWelder::new(tolerance);
+        let mut representatives: HashMap<usize, usize> = HashMap::new();
+        let mut remap: Vec<usize> = (0..self.vertices.len()).collect();
+        for &v in subset {
+            let welded = welder.insert(self.vertices[v]);
+            remap[v] = *representatives.entry(welded).or_insert(v);
+        }
+
+        for face in self.faces.iter_mut() {
+            *face = face.map(|v| remap[v]);
+        }
+    }
+
+    pub fn to_triangles(&self) -> Triangles {
+        self.faces
+            .iter()
+            .map(|face| Triangle::from_vertices(face.map(|i| self.vertices[i])))
+            .collect()
+    }
+
+    /// A face is degenerate when two of its corners were welded together.
+    pub fn is_degenerate(face: &[usize; 3]) -> bool {
+        face[0] == face[1] || face[1] == face[2] || face[2] == face[0]
+    }
+
+    /// Maps every undirected edge to the faces that use it.
+    pub fn edge_faces(&self) -> HashMap<Edge, Vec<usize>> {
+        let mut edges: HashMap<Edge, Vec<usize>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            if IndexedMesh::is_degenerate(face) {
+                continue;
+            }
+            for i in 0..3 {
+                edges
+                    .entry(edge(face[i], face[(i + 1) % 3]))
+                    .or_default()
+                    .push(f);
+            }
+        }
+        edges
+    }
+
+    /// Lists the non-degenerate faces around every vertex.
+    pub fn vertex_faces(&self) -> Vec<Vec<usize>> {
+        let mut vertex_faces = vec![Vec::new(); self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            if IndexedMesh::is_degenerate(face) {
+                continue;
+            }
+            for &v in face {
+                vertex_faces[v].push(f);
+            }
+        }
+        vertex_faces
+    }
+
+    /// Closed chains of boundary edges, following the winding of the adjacent faces.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let edges = self.edge_faces();
+        let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            if IndexedMesh::is_degenerate(face) {
+                continue;
+            }
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                if edges[&edge(a, b)] == [f] {
+                    next.entry(a).or_default().push(b);
+                }
+            }
+        }
+
+        let mut starts: Vec<usize> = next.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut loops = Vec::new();
+        for start in starts {
+            while let Some(mut current) = next.get_mut(&start).and_then(|out| out.pop()) {
+                let mut boundary = vec![start];
+                while current != start {
+                    boundary.push(current);
+                    match next.get_mut(&current).and_then(|out| out.pop()) {
+                        Some(v) => current = v,
+                        // open chain around a non-manifold vertex
+                        None => break,
+                    }
+                }
+                if current == start {
+                    loops.push(boundary);
+                }
+            }
+        }
+        loops
+    }
+
+    /// Whether `face` traverses the directed edge `a -> b`.
+    pub fn has_directed_edge(face: &[usize; 3], a: usize, b: usize) -> bool {
+        (0..3).any(|i| face[i] == a && face[(i + 1) % 3] == b)
+    }
+}
+
+/// Merges points that lie within `tolerance` of each other using a uniform grid.
+struct VertexWelder {
+    tolerance: Float,
+    vertices: Vec<Vec3>,
+    grid: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl VertexWelder {
+    fn new(tolerance: Float) -> Self {
+        VertexWelder {
+            tolerance: tolerance.max(Float::MIN_POSITIVE),
+            vertices: Vec::new(),
+            grid: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, vertex: Vec3) -> (i64, i64, i64) {
+        (
+            (vertex.x / self.tolerance).floor() as i64,
+            (vertex.y / self.tolerance).floor() as i64,
+            (vertex.z / self.tolerance).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, vertex: Vec3) -> usize {
+        let (cx, cy, cz) = self.cell(vertex);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(candidates) = self.grid.get(&(cx + dx, cy + dy, cz + dz)) {
+                        for &i in candidates {
+                            if (self.vertices[i] - vertex).d3() <= self.tolerance {
+                                return i;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let index = self.vertices.len();
+        self.vertices.push(vertex);
+        self.grid.entry((cx, cy, cz)).or_default().push(index);
+        index
+    }
+}
+
+/// Union-find over `0..n` with path compression.
+pub(crate) struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub(crate) fn new(n: usize) -> Self {
+        DisjointSet {
+            parent: (0..n).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a] = b;
+        }
+    }
+}
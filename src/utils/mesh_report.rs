@@ -0,0 +1,181 @@
+use std::{collections::HashMap, fmt};
+
+use super::{
+    mesh::{DisjointSet, IndexedMesh},
+    stl_parser::Triangles,
+};
+
+/// Topological summary of a mesh, used to decide whether it is printable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshReport {
+    pub facets: usize,
+    pub vertices: usize,
+    pub edges: usize,
+    /// Edges used by exactly one facet.
+    pub boundary_edges: usize,
+    /// Edges shared by more than two facets.
+    pub non_manifold_edges: usize,
+    /// Vertices whose surrounding facets do not form a single fan.
+    pub non_manifold_vertices: usize,
+    pub components: usize,
+    /// `V - E + F` over the welded mesh.
+    pub euler_characteristic: i64,
+    /// Total genus of all components, only defined for closed 2-manifolds.
+    pub genus: Option<i64>,
+}
+
+impl MeshReport {
+    pub fn new(triangles: &Triangles) -> Self {
+        let mesh = IndexedMesh::from_triangles(triangles);
+        let edges = mesh.edge_faces();
+        let vertex_faces = mesh.vertex_faces();
+
+        let boundary_edges = edges.values().filter(|faces| faces.len() == 1).count();
+        let non_manifold_edges = edges.values().filter(|faces| faces.len() > 2).count();
+
+        let non_manifold_vertices = vertex_faces
+            .iter()
+            .enumerate()
+            .filter(|(v, faces)| count_fans(&mesh, *v, faces) > 1)
+            .count();
+
+        let mut set = DisjointSet::new(mesh.vertices.len());
+        let mut facets = 0;
+        for face in mesh.faces.iter() {
+            if IndexedMesh::is_degenerate(face) {
+                continue;
+            }
+            facets += 1;
+            set.union(face[0], face[1]);
+            set.union(face[1], face[2]);
+        }
+
+        let used: Vec<usize> = (0..mesh.vertices.len())
+            .filter(|&v| !vertex_faces[v].is_empty())
+            .collect();
+        let mut roots: Vec<usize> = used.iter().map(|&v| set.find(v)).collect();
+        roots.sort_unstable();
+        roots.dedup();
+        let components = roots.len();
+
+        let euler_characteristic = used.len() as i64 - edges.len() as i64 + facets as i64;
+
+        let closed_manifold =
+            boundary_edges == 0 && non_manifold_edges == 0 && non_manifold_vertices == 0;
+        let genus = closed_manifold.then(|| (2 * components as i64 - euler_characteristic) / 2);
+
+        MeshReport {
+            facets,
+            vertices: used.len(),
+            edges: edges.len(),
+            boundary_edges,
+            non_manifold_edges,
+            non_manifold_vertices,
+            components,
+            euler_characteristic,
+            genus,
+        }
+    }
+
+    pub fn is_watertight(&self) -> bool {
+        self.boundary_edges == 0
+    }
+
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges == 0 && self.non_manifold_vertices == 0
+    }
+}
+
+/// Counts the groups of facets around `v` that are connected through edges incident to `v`.
+fn count_fans(mesh: &IndexedMesh, v: usize, faces: &[usize]) -> usize {
+    let mut set = DisjointSet::new(faces.len());
+    let mut first_use: HashMap<usize, usize> = HashMap::new();
+
+    for (i, &f) in faces.iter().enumerate() {
+        for &w in mesh.faces[f].iter().filter(|&&w| w != v) {
+            match first_use.get(&w) {
+                Some(&j) => set.union(i, j),
+                None => {
+                    first_use.insert(w, i);
+                }
+            }
+        }
+    }
+
+    let mut roots: Vec<usize> = (0..faces.len()).map(|i| set.find(i)).collect();
+    roots.sort_unstable();
+    roots.dedup();
+    roots.len()
+}
+
+impl fmt::Display for MeshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "facets:                {}", self.facets)?;
+        writeln!(f, "vertices:              {}", self.vertices)?;
+        writeln!(f, "edges:                 {}", self.edges)?;
+        writeln!(f, "boundary edges:        {}", self.boundary_edges)?;
+        writeln!(f, "non-manifold edges:    {}", self.non_manifold_edges)?;
+        writeln!(f, "non-manifold vertices: {}", self.non_manifold_vertices)?;
+        writeln!(f, "components:            {}", self.components)?;
+        writeln!(f, "euler characteristic:  {}", self.euler_characteristic)?;
+        match self.genus {
+            Some(genus) => writeln!(f, "genus:                 {}", genus)?,
+            None => writeln!(f, "genus:                 undefined")?,
+        }
+        writeln!(f, "watertight:            {}", self.is_watertight())?;
+        write!(f, "manifold:              {}", self.is_manifold())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixtures::tetrahedron, stl_parser::Triangle, vec3::Vec3};
+
+    #[test]
+    fn test_closed_mesh() {
+        let report = MeshReport::new(&tetrahedron(0.0));
+        assert_eq!(report.facets, 4);
+        assert_eq!(report.vertices, 4);
+        assert_eq!(report.edges, 6);
+        assert!(report.is_watertight());
+        assert!(report.is_manifold());
+        assert_eq!(report.euler_characteristic, 2);
+        assert_eq!(report.genus, Some(0));
+    }
+
+    #[test]
+    fn test_open_mesh() {
+        let mut triangles = tetrahedron(0.0);
+        triangles.pop();
+        let report = MeshReport::new(&triangles);
+        assert_eq!(report.boundary_edges, 3);
+        assert!(!report.is_watertight());
+        assert_eq!(report.genus, None);
+    }
+
+    #[test]
+    fn test_two_components_touching_at_a_vertex() {
+        let mut triangles = tetrahedron(0.0);
+        triangles.extend(tetrahedron(1.0));
+        let report = MeshReport::new(&triangles);
+        assert_eq!(report.components, 1);
+        assert_eq!(report.non_manifold_vertices, 1);
+        assert!(report.is_watertight());
+        assert!(!report.is_manifold());
+    }
+
+    #[test]
+    fn test_non_manifold_edge() {
+        let mut triangles = tetrahedron(0.0);
+        let extra: [Vec3; 3] = [
+            (0.0, 0.0, 0.0).into(),
+            (1.0, 0.0, 0.0).into(),
+            (0.5, -1.0, 0.0).into(),
+        ];
+        triangles.push(Triangle::from_vertices(extra));
+        let report = MeshReport::new(&triangles);
+        assert_eq!(report.non_manifold_edges, 1);
+        assert_eq!(report.components, 1);
+    }
+}
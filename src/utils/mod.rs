@@ -2,11 +2,50 @@ use std::path::PathBuf;
 
 use clap::Subcommand;
 
-use self::vec3::{Float, Vec3};
+use self::{
+    components::ComponentOrder,
+    contour_export::ContourFormat,
+    csg::BooleanOp,
+    smoothing::{SmoothingMethod, SmoothingWeights},
+    subdivision::SubdivisionScheme,
+    units::Unit,
+    vec3::{Float, Vec3},
+    voxel::{VoxelFormat, VoxelMode},
+};
 
+pub mod bounding_box;
+pub mod bvh;
+pub mod cleanup;
+pub mod components;
+pub mod containment;
+pub mod contour_export;
+pub mod convex_hull;
+pub mod csg;
+pub mod decimate;
+pub mod diff;
+#[cfg(test)]
+mod fixtures;
+pub mod gcode;
+pub mod hausdorff;
+pub mod holes;
+pub mod intersection;
+pub mod marching_cubes;
+pub mod mass_properties;
+pub mod matrix;
+pub mod merge;
+pub mod mesh;
+pub mod mesh_report;
+pub mod orientation;
+pub mod point;
+pub mod slicer;
+pub mod smoothing;
 pub mod stl_generator;
 pub mod stl_parser;
-mod vec3;
+pub mod subdivision;
+pub mod transform;
+pub mod units;
+pub mod vec3;
+pub mod voxel;
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 pub enum StlFormat {
@@ -29,6 +68,14 @@ fn length_is_positive(s: &str) -> Result<f32, String> {
     }
 }
 
+fn length_is_not_negative(s: &str) -> Result<f32, String> {
+    let len = s.parse::<f32>().map_err(|err| err.to_string())?;
+    if len.is_nan() || len < 0.0 {
+        return Err(format!("Length is: {} < 0:", len));
+    }
+    Ok(len)
+}
+
 #[derive(Debug, Subcommand, Clone)]
 pub enum Command {
     Cone {
@@ -47,5 +94,219 @@ pub enum Command {
     },
     Parse {
         input: PathBuf,
+        /// unit of the input, read from the header or guessed when omitted;
+        /// kept in the header of the output
+        #[arg(long)]
+        units: Option<Unit>,
+        /// convert the mesh to this unit and record it in the header
+        #[arg(long)]
+        to_units: Option<Unit>,
+    },
+    /// check that the mesh is watertight and manifold
+    Check {
+        input: PathBuf,
+        /// also look for intersecting facets
+        #[arg(long)]
+        self_intersections: bool,
+    },
+    /// repair common defects of scanned meshes
+    Repair {
+        input: PathBuf,
+        /// make the facet winding consistent and outward facing
+        #[arg(long)]
+        orient: bool,
+        /// close boundary loops
+        #[arg(long)]
+        fill_holes: bool,
+        /// largest hole, in boundary edges, that gets filled
+        #[arg(long, default_value_t = 64)]
+        max_hole_size: usize,
+        /// remove zero-area, collapsed and duplicate facets
+        #[arg(long)]
+        remove_degenerate: bool,
+        /// facets with a smaller area count as degenerate
+        #[arg(long, default_value_t = EPS, value_parser = length_is_not_negative)]
+        min_area: Float,
+        /// vertices closer than this are merged
+        #[arg(long, default_value_t = EPS, value_parser = length_is_not_negative)]
+        min_edge_length: Float,
+    },
+    /// write every connected part into its own file
+    Split {
+        input: PathBuf,
+        /// order of the numbered output files
+        #[arg(long)]
+        sort_by: Option<ComponentOrder>,
+    },
+    /// combine several files into one
+    Merge {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// offset of each input, in the order of the inputs
+        #[arg(long, allow_hyphen_values = true)]
+        translate: Vec<Vec3>,
+        /// snap open borders closer than this distance together
+        #[arg(long)]
+        weld: Option<Float>,
+    },
+    /// print volume, area, centroids and inertia of a mesh
+    Info {
+        input: PathBuf,
+        /// mass per unit volume
+        #[arg(long, default_value_t = 1.0)]
+        density: f64,
+    },
+    /// move, rotate, scale or mirror a mesh
+    ///
+    /// The steps run in this order: center, fit, scale, mirror, rotate,
+    /// translate, drop to floor.
+    Transform {
+        input: PathBuf,
+        /// offset added to every vertex
+        #[arg(long, allow_hyphen_values = true)]
+        translate: Option<Vec3>,
+        /// rotation in degrees around x, then y, then z
+        #[arg(long, allow_hyphen_values = true)]
+        rotate: Option<Vec3>,
+        /// scale factor along each axis
+        #[arg(long, allow_hyphen_values = true)]
+        scale: Option<Vec3>,
+        /// normal of the mirror plane through the origin
+        #[arg(long, allow_hyphen_values = true)]
+        mirror: Option<Vec3>,
+        /// move the bounding box center to the origin first
+        #[arg(long)]
+        center: bool,
+        /// uniformly scale the mesh to fit into a box of this size
+        #[arg(long)]
+        fit: Option<Vec3>,
+        /// finally move the mesh onto the z = 0 plane
+        #[arg(long)]
+        drop_to_floor: bool,
+    },
+    /// reduce the number of facets by quadric error edge collapses
+    Decimate {
+        input: PathBuf,
+        /// number of facets to keep
+        #[arg(long)]
+        target: Option<usize>,
+        /// fraction of the facets to keep
+        #[arg(long)]
+        ratio: Option<f64>,
+        /// largest quadric error (squared distance) a collapse may introduce
+        #[arg(long)]
+        max_error: Option<f64>,
+    },
+    /// refine a mesh by splitting every facet into four
+    Subdivide {
+        input: PathBuf,
+        /// subdivision rules
+        #[arg(long, default_value = "loop")]
+        scheme: SubdivisionScheme,
+        /// number of subdivision steps, each one quadruples the facet count
+        #[arg(long, default_value_t = 1)]
+        iterations: usize,
+    },
+    /// reduce noise by moving vertices towards their neighbours
+    Smooth {
+        input: PathBuf,
+        /// smoothing algorithm
+        #[arg(long, default_value = "taubin")]
+        method: SmoothingMethod,
+        /// weights of the neighbouring vertices
+        #[arg(long, default_value = "uniform")]
+        weights: SmoothingWeights,
+        /// number of smoothing steps
+        #[arg(long, default_value_t = 10)]
+        iterations: usize,
+        /// positive step factor
+        #[arg(long, default_value_t = 0.5)]
+        lambda: Float,
+        /// negative step factor of Taubin smoothing
+        #[arg(long, default_value_t = -0.53, allow_hyphen_values = true)]
+        mu: Float,
+        /// let vertices on open borders move as well
+        #[arg(long)]
+        free_boundary: bool,
+        /// keep vertices on edges sharper than this many degrees in place
+        #[arg(long)]
+        feature_angle: Option<Float>,
+    },
+    /// combine two closed meshes with a boolean operation
+    Boolean {
+        a: PathBuf,
+        b: PathBuf,
+        /// operation applied to the solids
+        #[arg(long)]
+        op: BooleanOp,
+    },
+    /// write the convex hull of a mesh
+    Hull { input: PathBuf },
+    /// cut a mesh into layers and write their contours
+    Slice {
+        input: PathBuf,
+        /// distance between the slicing planes
+        #[arg(long, value_parser = length_is_positive, conflicts_with = "height")]
+        layer_height: Option<Float>,
+        /// cut with the single plane at this height instead
+        #[arg(long, allow_hyphen_values = true)]
+        height: Option<f64>,
+        /// normal of the slicing planes
+        #[arg(long, default_value = "0 0 1", allow_hyphen_values = true)]
+        normal: Vec3,
+        /// format of the contour file
+        #[arg(long, default_value = "svg")]
+        format: ContourFormat,
+        /// write one numbered file per layer
+        #[arg(long)]
+        split_layers: bool,
+    },
+    /// plan perimeters and infill and write Marlin G-code
+    Gcode {
+        input: PathBuf,
+        /// file with `key = value` print settings
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        settings: Option<PathBuf>,
+    },
+    /// convert a mesh into voxels
+    Voxelize {
+        input: PathBuf,
+        /// edge length of a voxel
+        #[arg(long, value_parser = length_is_positive)]
+        size: Float,
+        /// which voxels to set
+        #[arg(long, default_value = "solid")]
+        mode: VoxelMode,
+        /// format of the voxel file
+        #[arg(long, default_value = "stl")]
+        format: VoxelFormat,
+    },
+    /// measure the geometric deviation between two meshes
+    Compare {
+        a: PathBuf,
+        b: PathBuf,
+        /// random surface points per mesh on top of the vertices
+        #[arg(long, default_value_t = 10000)]
+        samples: usize,
+        /// seed of the random surface samples
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// fail when the Hausdorff distance exceeds this
+        #[arg(long, value_parser = length_is_positive)]
+        tolerance: Option<Float>,
+        /// write the deviation of every vertex of A to this CSV file
+        #[arg(long, value_hint = clap::ValueHint::FilePath)]
+        per_vertex: Option<PathBuf>,
+    },
+    /// report the facets that differ between two meshes
+    Diff {
+        a: PathBuf,
+        b: PathBuf,
+        /// largest coordinate difference of matching vertices
+        #[arg(long, default_value_t = 1e-5, value_parser = length_is_not_negative)]
+        tolerance: Float,
+        /// print the report as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
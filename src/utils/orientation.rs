@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use super::{
+    mesh::{edge, IndexedMesh},
+    point::{cross, dot, to_f64},
+    stl_parser::Triangles,
+};
+
+/// Makes the winding of every connected component consistent and outward facing.
+///
+/// The winding of the first facet of a component is propagated to its
+/// neighbours across manifold edges, then the whole component is flipped if
+/// its signed volume is negative. Returns the number of flipped facets.
+pub fn orient_facets(triangles: &mut Triangles) -> usize {
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let edges = mesh.edge_faces();
+
+    let mut flipped: Vec<Option<bool>> = vec![None; mesh.faces.len()];
+    let mut queue = VecDeque::new();
+
+    for start in 0..mesh.faces.len() {
+        if flipped[start].is_some() || IndexedMesh::is_degenerate(&mesh.faces[start]) {
+            continue;
+        }
+
+        flipped[start] = Some(false);
+        queue.push_back(start);
+        let mut component = Vec::new();
+
+        while let Some(f) = queue.pop_front() {
+            component.push(f);
+            let face = mesh.faces[f];
+            let f_flipped = flipped[f] == Some(true);
+
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                let neighbours = &edges[&edge(a, b)];
+                // propagating across non-manifold edges is ambiguous
+                if neighbours.len() != 2 {
+                    continue;
+                }
+                for &g in neighbours {
+                    if g == f || flipped[g].is_some() {
+                        continue;
+                    }
+                    // `f` effectively walks a -> b, so `g` has to walk b -> a
+                    let g_has_ab = IndexedMesh::has_directed_edge(&mesh.faces[g], a, b);
+                    flipped[g] = Some(g_has_ab != f_flipped);
+                    queue.push_back(g);
+                }
+            }
+        }
+
+        let volume: f64 = component
+            .iter()
+            .map(|&f| {
+                let volume = signed_volume_contribution(&mesh, f);
+                if flipped[f] == Some(true) {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .sum();
+
+        if volume < 0.0 {
+            for &f in &component {
+                flipped[f] = flipped[f].map(|flip| !flip);
+            }
+        }
+    }
+
+    let mut count = 0;
+    for (triangle, flip) in triangles.iter_mut().zip(flipped) {
+        if flip == Some(true) {
+            triangle.flip();
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Signed volume of the tetrahedron spanned by the face and the origin.
+fn signed_volume_contribution(mesh: &IndexedMesh, f: usize) -> f64 {
+    let [a, b, c] = mesh.faces[f].map(|i| to_f64(mesh.vertices[i]));
+    dot(a, cross(b, c)) / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::tetrahedron;
+
+    fn volume(triangles: &Triangles) -> f64 {
+        let mesh = IndexedMesh::from_triangles(triangles);
+        (0..mesh.faces.len())
+            .map(|f| signed_volume_contribution(&mesh, f))
+            .sum()
+    }
+
+    #[test]
+    fn test_consistent_mesh_is_untouched() {
+        let mut triangles = tetrahedron(0.0);
+        assert_eq!(orient_facets(&mut triangles), 0);
+        assert!((volume(&triangles) - 1.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_single_flipped_facet() {
+        let mut triangles = tetrahedron(0.0);
+        triangles[2].flip();
+        assert_eq!(orient_facets(&mut triangles), 1);
+        assert!((volume(&triangles) - 1.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_inside_out_mesh() {
+        let mut triangles = tetrahedron(0.0);
+        triangles.iter_mut().for_each(|t| t.flip());
+        triangles[0].flip();
+        assert_eq!(orient_facets(&mut triangles), 3);
+        assert!((volume(&triangles) - 1.0 / 6.0).abs() < 1e-6);
+    }
+}
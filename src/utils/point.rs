@@ -0,0 +1,42 @@
+//! Plain `f64` vectors for geometric predicates that need more precision than
+//! [`Vec3`].
+
+use super::vec3::{Float, Vec3};
+
+pub type Point = [f64; 3];
+
+pub fn to_f64(v: Vec3) -> Point {
+    [v.x as f64, v.y as f64, v.z as f64]
+}
+
+pub fn to_vec3(p: Point) -> Vec3 {
+    Vec3::from((p[0] as Float, p[1] as Float, p[2] as Float))
+}
+
+pub fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale_by(a: Point, s: f64) -> Point {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub fn dot(a: Point, b: Point) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn length(a: Point) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub fn lerp(a: Point, b: Point, t: f64) -> Point {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
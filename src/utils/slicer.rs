@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    mesh::{edge, Edge, IndexedMesh},
+    point::{dot, lerp, sub, to_f64},
+    stl_parser::Triangles,
+    vec3::{Float, Vec3},
+};
+
+/// Point in the coordinate frame of a slicing plane.
+pub type Point2 = [f64; 2];
+
+/// Closed or open polyline in the plane of a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Point2>,
+    /// `false` when the mesh has open borders that cut the polyline short.
+    pub closed: bool,
+    /// Closed contours nested inside an odd number of others bound holes.
+    /// Outer contours run counter-clockwise, holes clockwise.
+    pub is_hole: bool,
+    /// Index of the smallest contour of the layer that encloses this one.
+    pub parent: Option<usize>,
+}
+
+impl Contour {
+    /// Shoelace area, positive for counter-clockwise contours.
+    pub fn signed_area(&self) -> f64 {
+        let n = self.points.len();
+        (0..n)
+            .map(|i| {
+                let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+                a[0] * b[1] - b[0] * a[1]
+            })
+            .sum::<f64>()
+            / 2.0
+    }
+
+    /// Even-odd test against the closed polyline.
+    pub fn contains(&self, p: Point2) -> bool {
+        let n = self.points.len();
+        let mut inside = false;
+        for i in 0..n {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            if (a[1] > p[1]) != (b[1] > p[1]) {
+                let x = a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+                if p[0] < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+/// Cross-section of a mesh with one plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// Signed distance of the plane from the origin along its normal.
+    pub height: f64,
+    /// Point of the plane that maps to `[0, 0]`.
+    pub origin: Vec3,
+    /// In-plane axes of the 2D coordinates, `u x v` is the plane normal.
+    pub u: Vec3,
+    pub v: Vec3,
+    pub contours: Vec<Contour>,
+}
+
+impl Layer {
+    /// Enclosed area: outer contours minus holes.
+    pub fn area(&self) -> f64 {
+        self.contours
+            .iter()
+            .filter(|contour| contour.closed)
+            .map(Contour::signed_area)
+            .sum()
+    }
+
+    pub fn to_3d(&self, p: Point2) -> Vec3 {
+        self.origin + self.u * p[0] as Float + self.v * p[1] as Float
+    }
+}
+
+/// In-plane axes for `normal`; a z normal gives the usual x and y axes.
+pub fn plane_frame(normal: Vec3) -> (Vec3, Vec3) {
+    let normal = normal.normalize();
+    let axis = if normal.x.abs() < 0.9 {
+        Vec3::from((1.0, 0.0, 0.0))
+    } else {
+        Vec3::from((0.0, 1.0, 0.0))
+    };
+    let u = (axis - normal * normal.dot(axis)).normalize();
+    (u, normal.cross(u))
+}
+
+/// Intersects the mesh with the plane `normal . p = height`.
+pub fn slice(triangles: &Triangles, normal: Vec3, height: f64) -> Layer {
+    slice_mesh(&IndexedMesh::from_triangles(triangles), normal, height)
+}
+
+/// Slices the mesh with parallel planes `layer_height` apart, the first one
+/// half a layer above the lowest point along `normal`.
+///
+/// `layer_height` must be positive and finite, otherwise there are no layers.
+pub fn slice_layers(triangles: &Triangles, normal: Vec3, layer_height: f64) -> Vec<Layer> {
+    if !(layer_height.is_finite() && layer_height > 0.0) {
+        return Vec::new();
+    }
+    let mesh = IndexedMesh::from_triangles(triangles);
+    let n = normal.normalize();
+    let heights = mesh.vertices.iter().map(|&v| n.dot(v) as f64);
+    let (min, max) = heights.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), h| {
+        (min.min(h), max.max(h))
+    });
+
+    // counted rather than accumulated, so that a step below the resolution
+    // of `height` still ends the loop
+    (0..)
+        .map(|i| min + (i as f64 + 0.5) * layer_height)
+        .take_while(|&height| height < max)
+        .map(|height| slice_mesh(&mesh, normal, height))
+        .collect()
+}
+
+fn slice_mesh(mesh: &IndexedMesh, normal: Vec3, height: f64) -> Layer {
+    let n = normal.normalize();
+    let (u, v) = plane_frame(n);
+    let origin = n * height as Float;
+    let (n64, u64, v64, o64) = (to_f64(n), to_f64(u), to_f64(v), to_f64(origin));
+
+    // vertices exactly on the plane count as above, so every crossing edge
+    // has one endpoint strictly on each side
+    let distances: Vec<f64> = mesh
+        .vertices
+        .iter()
+        .map(|&p| dot(n64, to_f64(p)) - height)
+        .collect();
+    let above = |v: usize| distances[v] >= 0.0;
+
+    let crossing = |e: Edge| -> Point2 {
+        let (a, b) = (to_f64(mesh.vertices[e.0]), to_f64(mesh.vertices[e.1]));
+        let t = distances[e.0] / (distances[e.0] - distances[e.1]);
+        let p = sub(lerp(a, b, t), o64);
+        [dot(p, u64), dot(p, v64)]
+    };
+
+    // segments run from the edge where the winding goes down through the
+    // plane to the edge where it comes back up, which leaves the material on
+    // the left for outward facing facets
+    let mut segments: HashMap<Edge, Edge> = HashMap::new();
+    for face in &mesh.faces {
+        if IndexedMesh::is_degenerate(face) {
+            continue;
+        }
+        let (mut down, mut up) = (None, None);
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            match (above(a), above(b)) {
+                (true, false) => down = Some(edge(a, b)),
+                (false, true) => up = Some(edge(a, b)),
+                _ => {}
+            }
+        }
+        if let (Some(down), Some(up)) = (down, up) {
+            segments.insert(down, up);
+        }
+    }
+
+    let mut contours: Vec<Contour> = chain(segments)
+        .into_iter()
+        .map(|(edges, closed)| Contour {
+            points: edges.into_iter().map(crossing).collect(),
+            closed,
+            is_hole: false,
+            parent: None,
+        })
+        .collect();
+    classify(&mut contours);
+
+    Layer {
+        height,
+        origin,
+        u,
+        v,
+        contours,
+    }
+}
+
+/// Links segments into polylines, starting with the open chains.
+fn chain(mut segments: HashMap<Edge, Edge>) -> Vec<(Vec<Edge>, bool)> {
+    let ends: HashSet<Edge> = segments.values().copied().collect();
+    let mut starts: Vec<Edge> = segments.keys().copied().collect();
+    // heads of open chains first, then the rest in a stable order
+    starts.sort_by_key(|start| (ends.contains(start), *start));
+
+    let mut polylines = Vec::new();
+    for start in starts {
+        if !segments.contains_key(&start) {
+            continue;
+        }
+        let mut edges = vec![start];
+        let mut current = start;
+        let mut closed = false;
+        while let Some(next) = segments.remove(&current) {
+            if next == start {
+                closed = true;
+                break;
+            }
+            edges.push(next);
+            current = next;
+        }
+        closed &= edges.len() > 2;
+        polylines.push((edges, closed));
+    }
+    polylines
+}
+
+/// Marks holes by nesting depth and orients outer contours counter-clockwise
+/// and holes clockwise, independent of the facet orientation.
+fn classify(contours: &mut [Contour]) {
+    let areas: Vec<f64> = contours
+        .iter()
+        .map(|contour| contour.signed_area().abs())
+        .collect();
+
+    for i in 0..contours.len() {
+        if !contours[i].closed {
+            continue;
+        }
+        let p = contours[i].points[0];
+        let enclosing: Vec<usize> = (0..contours.len())
+            .filter(|&j| j != i && contours[j].closed && areas[j] > areas[i])
+            .filter(|&j| contours[j].contains(p))
+            .collect();
+
+        let contour = &mut contours[i];
+        contour.is_hole = enclosing.len() % 2 == 1;
+        contour.parent = enclosing
+            .into_iter()
+            .min_by(|&a, &b| areas[a].total_cmp(&areas[b]));
+        if (contour.signed_area() < 0.0) != contour.is_hole {
+            contour.points.reverse();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::cuboid;
+
+    #[test]
+    fn test_box_section() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (2.0, 1.0, 1.0).into());
+        let layer = slice(&triangles, (0.0, 0.0, 1.0).into(), 0.5);
+
+        assert_eq!(layer.contours.len(), 1);
+        let contour = &layer.contours[0];
+        assert!(contour.closed && !contour.is_hole);
+        assert_eq!(contour.points.len(), 8);
+        assert!((layer.area() - 2.0).abs() < 1e-6);
+        assert!(contour.contains([1.5, 0.5]));
+        assert_eq!(layer.to_3d(contour.points[0]).z, 0.5);
+    }
+
+    #[test]
+    fn test_layer_heights() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (2.0, 1.0, 1.0).into());
+        let layers = slice_layers(&triangles, (0.0, 0.0, 1.0).into(), 0.25);
+        let heights: Vec<f64> = layers.iter().map(|layer| layer.height).collect();
+        assert_eq!(heights, [0.125, 0.375, 0.625, 0.875]);
+
+        for layer_height in [0.0, -0.25, f64::NAN, f64::INFINITY] {
+            assert!(slice_layers(&triangles, (0.0, 0.0, 1.0).into(), layer_height).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_hole_classification() {
+        let mut triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        // inner box with inward facing facets, and one more inside it
+        let mut cavity = cuboid((0.25, 0.25, 0.0).into(), (0.75, 0.75, 1.0).into());
+        cavity.iter_mut().for_each(|t| t.vertices.swap(1, 2));
+        triangles.extend(cavity);
+        triangles.extend(cuboid((0.4, 0.4, 0.0).into(), (0.6, 0.6, 1.0).into()));
+
+        let layers = slice_layers(&triangles, (0.0, 0.0, 1.0).into(), 0.25);
+        assert_eq!(layers.len(), 4);
+        for layer in &layers {
+            assert_eq!(layer.contours.len(), 3);
+            let holes: Vec<&Contour> = layer.contours.iter().filter(|c| c.is_hole).collect();
+            assert_eq!(holes.len(), 1);
+            assert!(holes[0].signed_area() < 0.0);
+            assert!((layer.area() - (1.0 - 0.25 + 0.04)).abs() < 1e-6);
+
+            let island = layer
+                .contours
+                .iter()
+                .position(|c| (c.signed_area() - 0.04).abs() < 1e-6)
+                .unwrap();
+            let parent = layer.contours[island].parent.unwrap();
+            assert!(layer.contours[parent].is_hole);
+        }
+    }
+
+    #[test]
+    fn test_open_mesh() {
+        let triangles: Triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into())
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !(4..6).contains(i))
+            .map(|(_, t)| t)
+            .collect();
+        let layer = slice(&triangles, (0.0, 0.0, 1.0).into(), 0.5);
+        assert_eq!(layer.contours.len(), 1);
+        assert!(!layer.contours[0].closed);
+        assert_eq!(layer.contours[0].points.len(), 7);
+        assert_eq!(layer.area(), 0.0);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use super::{
+    mesh::{Edge, IndexedMesh},
+    stl_parser::Triangles,
+    vec3::{Float, Vec3},
+    EPS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SmoothingMethod {
+    /// move every vertex towards the average of its neighbours, shrinks the mesh
+    Laplacian,
+    /// alternate a shrinking and an inflating step to keep the volume
+    #[default]
+    Taubin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SmoothingWeights {
+    /// every neighbour counts the same
+    #[default]
+    Uniform,
+    /// cotangent weights, less sensitive to irregular triangulations
+    Cotangent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothingOptions {
+    pub method: SmoothingMethod,
+    pub weights: SmoothingWeights,
+    pub iterations: usize,
+    /// Positive step factor.
+    pub lambda: Float,
+    /// Negative step factor of Taubin smoothing, `|mu|` slightly above `lambda`.
+    pub mu: Float,
+    /// Keep vertices on open borders and non-manifold edges in place.
+    pub pin_boundary: bool,
+    /// Keep vertices on edges whose dihedral angle exceeds this many degrees in place.
+    pub feature_angle: Option<Float>,
+}
+
+impl Default for SmoothingOptions {
+    fn default() -> Self {
+        SmoothingOptions {
+            method: SmoothingMethod::default(),
+            weights: SmoothingWeights::default(),
+            iterations: 10,
+            lambda: 0.5,
+            mu: -0.53,
+            pin_boundary: true,
+            feature_angle: None,
+        }
+    }
+}
+
+/// Smooths the mesh over the one-ring of every vertex, keeping the facet order.
+///
+/// Returns the number of pinned vertices.
+pub fn smooth(triangles: &mut Triangles, options: &SmoothingOptions) -> usize {
+    let mut mesh = IndexedMesh::from_triangles(triangles);
+    // the topology never changes, only the positions
+    let edges = mesh.edge_faces();
+    let pinned = pinned_vertices(&mesh, &edges, options);
+
+    let steps: &[Float] = match options.method {
+        SmoothingMethod::Laplacian => &[options.lambda],
+        SmoothingMethod::Taubin => &[options.lambda, options.mu],
+    };
+    for _ in 0..options.iterations {
+        for &factor in steps {
+            let laplacian = laplacian(&mesh, &edges, options.weights);
+            for ((vertex, delta), &pinned) in mesh.vertices.iter_mut().zip(laplacian).zip(&pinned) {
+                if !pinned {
+                    *vertex = *vertex + delta * factor;
+                }
+            }
+        }
+    }
+
+    for (triangle, face) in triangles.iter_mut().zip(&mesh.faces) {
+        triangle.vertices = face.map(|v| mesh.vertices[v]);
+        triangle.normal = triangle.unit_normal();
+    }
+    pinned.iter().filter(|&&pinned| pinned).count()
+}
+
+fn pinned_vertices(
+    mesh: &IndexedMesh,
+    edges: &HashMap<Edge, Vec<usize>>,
+    options: &SmoothingOptions,
+) -> Vec<bool> {
+    let mut pinned = vec![false; mesh.vertices.len()];
+    let normals: Vec<Vec3> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.map(|v| mesh.vertices[v]);
+            let normal = (b - a).cross(c - a);
+            if normal.d3() < EPS {
+                normal
+            } else {
+                normal.normalize()
+            }
+        })
+        .collect();
+    let cos_limit = options.feature_angle.map(|angle| angle.to_radians().cos());
+
+    for (&(a, b), faces) in edges {
+        let pin = match faces[..] {
+            [f, g] => cos_limit.is_some_and(|limit| normals[f].dot(normals[g]) < limit),
+            _ => options.pin_boundary,
+        };
+        if pin {
+            pinned[a] = true;
+            pinned[b] = true;
+        }
+    }
+    pinned
+}
+
+/// Weighted average of the neighbours minus the vertex itself.
+fn laplacian(
+    mesh: &IndexedMesh,
+    edges: &HashMap<Edge, Vec<usize>>,
+    weights: SmoothingWeights,
+) -> Vec<Vec3> {
+    let zero = Vec3::from((0.0, 0.0, 0.0));
+    let mut uniform = vec![(zero, 0.0); mesh.vertices.len()];
+    let mut cotangent = vec![(zero, 0.0); mesh.vertices.len()];
+
+    for (&(a, b), faces) in edges {
+        let (pa, pb) = (mesh.vertices[a], mesh.vertices[b]);
+        uniform[a] = (uniform[a].0 + pb, uniform[a].1 + 1.0);
+        uniform[b] = (uniform[b].0 + pa, uniform[b].1 + 1.0);
+
+        if weights == SmoothingWeights::Cotangent {
+            let weight: Float = faces
+                .iter()
+                .map(|&f| {
+                    let c = mesh.faces[f]
+                        .into_iter()
+                        .find(|&v| v != a && v != b)
+                        .unwrap();
+                    let (u, v) = (pa - mesh.vertices[c], pb - mesh.vertices[c]);
+                    // sine of the angle, independent of the edge lengths
+                    let area = u.cross(v).d3();
+                    if area < EPS * u.d3() * v.d3() {
+                        0.0
+                    } else {
+                        u.dot(v) / area / 2.0
+                    }
+                })
+                .sum::<Float>()
+                // negative weights around obtuse angles make the step unstable
+                .max(0.0);
+            cotangent[a] = (cotangent[a].0 + pb * weight, cotangent[a].1 + weight);
+            cotangent[b] = (cotangent[b].0 + pa * weight, cotangent[b].1 + weight);
+        }
+    }
+
+    mesh.vertices
+        .iter()
+        .enumerate()
+        .map(|(v, &position)| {
+            let (sum, total) = if cotangent[v].1 > EPS {
+                cotangent[v]
+            } else {
+                uniform[v]
+            };
+            if total > 0.0 {
+                sum / total - position
+            } else {
+                zero
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        mass_properties::MassProperties,
+        stl_parser::Triangle,
+        subdivision::{subdivide, SubdivisionScheme},
+    };
+
+    /// Octahedron refined into a coarse sphere of radius 1.
+    fn sphere() -> Triangles {
+        let p: [Vec3; 6] = [
+            (1.0, 0.0, 0.0).into(),
+            (-1.0, 0.0, 0.0).into(),
+            (0.0, 1.0, 0.0).into(),
+            (0.0, -1.0, 0.0).into(),
+            (0.0, 0.0, 1.0).into(),
+            (0.0, 0.0, -1.0).into(),
+        ];
+        let mut triangles: Triangles = [
+            [0, 2, 4],
+            [2, 1, 4],
+            [1, 3, 4],
+            [3, 0, 4],
+            [2, 0, 5],
+            [1, 2, 5],
+            [3, 1, 5],
+            [0, 3, 5],
+        ]
+        .iter()
+        .map(|f| Triangle::from_vertices(f.map(|i| p[i])))
+        .collect();
+        subdivide(&mut triangles, SubdivisionScheme::Midpoint, 2);
+        for triangle in triangles.iter_mut() {
+            triangle.vertices = triangle.vertices.map(Vec3::normalize);
+        }
+        triangles
+    }
+
+    fn volume(triangles: &Triangles) -> f64 {
+        MassProperties::new(triangles, 1.0).volume
+    }
+
+    #[test]
+    fn test_taubin_shrinks_less_than_laplacian() {
+        let original = volume(&sphere());
+        for weights in [SmoothingWeights::Uniform, SmoothingWeights::Cotangent] {
+            let mut laplacian = sphere();
+            let options = SmoothingOptions {
+                method: SmoothingMethod::Laplacian,
+                weights,
+                ..Default::default()
+            };
+            assert_eq!(smooth(&mut laplacian, &options), 0);
+
+            let mut taubin = sphere();
+            let options = SmoothingOptions {
+                method: SmoothingMethod::Taubin,
+                ..options
+            };
+            smooth(&mut taubin, &options);
+
+            let laplacian_loss = original - volume(&laplacian);
+            let taubin_loss = (original - volume(&taubin)).abs();
+            assert!(laplacian_loss > 0.0);
+            assert!(taubin_loss < laplacian_loss / 4.0);
+        }
+    }
+
+    #[test]
+    fn test_pinned_vertices_do_not_move() {
+        let mut triangles: Triangles = sphere()
+            .into_iter()
+            .filter(|t| t.vertices.iter().any(|v| v.z < 0.5))
+            .collect();
+        let mesh = IndexedMesh::from_triangles(&triangles);
+        let border: Vec<Vec3> = mesh
+            .boundary_loops()
+            .concat()
+            .into_iter()
+            .map(|v| mesh.vertices[v])
+            .collect();
+
+        let pinned = smooth(&mut triangles, &SmoothingOptions::default());
+        assert_eq!(pinned, border.len());
+        for position in border {
+            assert!(triangles.iter().any(|t| t.vertices.contains(&position)));
+        }
+
+        // every edge of the sphere bends by more than a degree
+        let mut triangles = sphere();
+        let options = SmoothingOptions {
+            feature_angle: Some(1.0),
+            ..Default::default()
+        };
+        smooth(&mut triangles, &options);
+        for (smoothed, original) in triangles.iter().zip(sphere()) {
+            assert_eq!(smoothed.vertices, original.vertices);
+        }
+    }
+
+    #[test]
+    fn test_cotangent_weights_do_not_depend_on_scale() {
+        let options = SmoothingOptions {
+            method: SmoothingMethod::Laplacian,
+            weights: SmoothingWeights::Cotangent,
+            ..Default::default()
+        };
+        let mut unit = sphere();
+        smooth(&mut unit, &options);
+
+        // a thousandth of the size, with facets far below `EPS` in area
+        let scale = 1e-3;
+        let mut small = sphere();
+        for triangle in small.iter_mut() {
+            triangle.vertices = triangle.vertices.map(|v| v * scale);
+        }
+        smooth(&mut small, &options);
+
+        for (small, unit) in small.iter().zip(&unit) {
+            for (p, q) in small.vertices.iter().zip(unit.vertices) {
+                assert!((*p / scale - q).d3() < 1e-4);
+            }
+        }
+    }
+}
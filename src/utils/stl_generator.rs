@@ -9,7 +9,7 @@ use super::{
 pub struct StlGenerator;
 
 impl StlGenerator {
-    fn generate_cube(a: Float, origin: Vec3) -> Triangles {
+    pub fn generate_cube(a: Float, origin: Vec3) -> Triangles {
         let mut v: Vec<Vec3> = Vec::new();
 
         for dx in [-1.0, 1.0] {
@@ -95,7 +95,7 @@ impl StlGenerator {
         Ok(())
     }
 
-    fn generate_cone(n: usize, r: Float, h: Float, origin: Vec3) -> Triangles {
+    pub fn generate_cone(n: usize, r: Float, h: Float, origin: Vec3) -> Triangles {
         let mut v: Vec<Vec3> = Vec::new();
 
         let top = origin + Vec3::from((0.0f32, 0.0f32, h));
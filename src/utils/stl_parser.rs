@@ -8,7 +8,7 @@ use super::vec3::Vec3;
 
 use anyhow::{anyhow, Context};
 
-use super::vec3::Float;
+use super::{vec3::Float, StlFormat, EPS};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Triangle {
@@ -16,6 +16,49 @@ pub struct Triangle {
     pub vertices: [Vec3; 3],
 }
 
+impl Triangle {
+    /// Creates a triangle whose normal follows the winding order of `vertices`.
+    pub fn from_vertices(vertices: [Vec3; 3]) -> Self {
+        let mut triangle = Triangle {
+            normal: Vec3::from((0.0, 0.0, 0.0)),
+            vertices,
+        };
+        triangle.normal = triangle.unit_normal();
+        triangle
+    }
+
+    /// Twice the area vector of the triangle (not normalized).
+    pub fn area_vector(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (b - a).cross(c - a)
+    }
+
+    pub fn area(&self) -> Float {
+        self.area_vector().d3() / 2.0
+    }
+
+    /// Signed volume of the tetrahedron spanned by the triangle and the origin.
+    pub fn signed_volume(&self) -> Float {
+        let [a, b, c] = self.vertices;
+        a.dot(b.cross(c)) / 6.0
+    }
+
+    /// Normal derived from the winding order, zero for degenerate triangles.
+    pub fn unit_normal(&self) -> Vec3 {
+        let n = self.area_vector();
+        if n.d3() < EPS {
+            return Vec3::from((0.0, 0.0, 0.0));
+        }
+        n.normalize()
+    }
+
+    /// Reverses the winding order and the normal.
+    pub fn flip(&mut self) {
+        self.vertices.swap(1, 2);
+        self.normal = -self.normal;
+    }
+}
+
 pub type Triangles = Vec<Triangle>;
 
 pub struct StlParser;
@@ -26,13 +69,11 @@ impl StlParser {
             Ok(triangles) => Ok(triangles),
             Err(err_bin) => match StlParser::read_ascii_stl(path) {
                 Ok(triangles) => Ok(triangles),
-                Err(err) => {
-                    Err(anyhow::anyhow!(
+                Err(err) => Err(anyhow::anyhow!(
                     "Failed to read STL file in either binary or ascii format!\nErrors:\n{}\n{}",
                     err_bin,
                     err
-                ))
-                }
+                )),
             },
         }
     }
@@ -43,18 +84,18 @@ impl StlParser {
         let mut triangles = Triangles::new();
         let mut header = [0u8; 80];
         file.read_exact(&mut header)
-            .with_context(|| format!("Failed to read header from binary STL!"))?;
+            .context("Failed to read header from binary STL!")?;
 
         let mut num_triangles_bytes = [0u8; 4];
         file.read_exact(&mut num_triangles_bytes)
-            .with_context(|| format!("Failed to read number of triangles from binary STL!"))?;
+            .context("Failed to read number of triangles from binary STL!")?;
 
         let num_triangles = u32::from_le_bytes(num_triangles_bytes);
 
         for _i in 0..num_triangles {
             let mut buffer = [0u8; 50];
             file.read_exact(&mut buffer)
-                .with_context(|| format!("Failed to read triangle from binary STL!"))?;
+                .context("Failed to read triangle from binary STL!")?;
 
             let normal: Vec3 = (
                 f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]),
@@ -64,21 +105,21 @@ impl StlParser {
                 .into();
 
             let mut vertices = [[0.0; 3]; 3];
-            for i in 0..3 {
+            for (i, vertex) in vertices.iter_mut().enumerate() {
                 let offset = 12 + i * 12;
-                vertices[i][0] = f32::from_le_bytes([
+                vertex[0] = f32::from_le_bytes([
                     buffer[offset],
                     buffer[offset + 1],
                     buffer[offset + 2],
                     buffer[offset + 3],
                 ]);
-                vertices[i][1] = f32::from_le_bytes([
+                vertex[1] = f32::from_le_bytes([
                     buffer[offset + 4],
                     buffer[offset + 5],
                     buffer[offset + 6],
                     buffer[offset + 7],
                 ]);
-                vertices[i][2] = f32::from_le_bytes([
+                vertex[2] = f32::from_le_bytes([
                     buffer[offset + 8],
                     buffer[offset + 9],
                     buffer[offset + 10],
@@ -95,7 +136,49 @@ impl StlParser {
         Ok(triangles)
     }
 
+    pub fn write_stl(path: PathBuf, triangles: Triangles, format: StlFormat) -> anyhow::Result<()> {
+        StlParser::write_stl_with_header(path, triangles, format, "")
+    }
+
+    /// Writes `header` into the header of binary files or after the solid name
+    /// of ASCII files.
+    pub fn write_stl_with_header(
+        path: PathBuf,
+        triangles: Triangles,
+        format: StlFormat,
+        header: &str,
+    ) -> anyhow::Result<()> {
+        match format {
+            StlFormat::ASCII => StlParser::write_to_ascii_file_with_header(path, triangles, header),
+            StlFormat::Binary => {
+                StlParser::write_to_binary_file_with_header(path, triangles, header)
+            }
+        }
+    }
+
+    /// Reads the 80 byte header of a binary STL, without trailing padding.
+    pub fn read_binary_header(path: PathBuf) -> anyhow::Result<String> {
+        let mut file = File::open(path.clone())
+            .with_context(|| format!("Failed to open file \'{}\'!", path.display()))?;
+        let mut header = [0u8; 80];
+        file.read_exact(&mut header)
+            .context("Failed to read header from binary STL!")?;
+
+        Ok(String::from_utf8_lossy(&header)
+            .trim_end_matches(['\0', ' '])
+            .to_string())
+    }
+
     pub fn write_to_binary_file(path: PathBuf, triangles: Triangles) -> anyhow::Result<()> {
+        StlParser::write_to_binary_file_with_header(path, triangles, "")
+    }
+
+    /// Writes a binary STL whose header starts with `header`, truncated to 80 bytes.
+    pub fn write_to_binary_file_with_header(
+        path: PathBuf,
+        triangles: Triangles,
+        header: &str,
+    ) -> anyhow::Result<()> {
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
@@ -108,30 +191,31 @@ impl StlParser {
                 )
             })?;
 
-        let header = [0u8; 80];
-        file.write_all(&header)
-            .with_context(|| format!("Failed to write header into binary STL!"))?;
+        let mut header_bytes = [0u8; 80];
+        let length = header.len().min(header_bytes.len());
+        header_bytes[..length].copy_from_slice(&header.as_bytes()[..length]);
+        file.write_all(&header_bytes)
+            .context("Failed to write header into binary STL!")?;
 
         let num_triangles = triangles.len() as u32;
         file.write_all(&num_triangles.to_le_bytes())
-            .with_context(|| format!("Failed to write number of triangles into binary STL!"))?;
+            .context("Failed to write number of triangles into binary STL!")?;
 
         for triangle in triangles {
             triangle
                 .normal
                 .write_le_bytes(&mut file)
-                .with_context(|| format!("Failed to write normal of triangle into binary STL!"))?;
+                .context("Failed to write normal of triangle into binary STL!")?;
 
             for vertex in triangle.vertices {
-                vertex.write_le_bytes(&mut file).with_context(|| {
-                    format!("Failed to write vertex of triangle into binary STL!")
-                })?;
+                vertex
+                    .write_le_bytes(&mut file)
+                    .context("Failed to write vertex of triangle into binary STL!")?;
             }
             // Write the attribute byte count (set to zero)
             let attribute_byte_count: [u8; 2] = [0, 0];
-            file.write_all(&attribute_byte_count).with_context(|| {
-                format!("Failed to write attribute byte count into binary STL!")
-            })?;
+            file.write_all(&attribute_byte_count)
+                .context("Failed to write attribute byte count into binary STL!")?;
         }
 
         Ok(())
@@ -156,6 +240,8 @@ impl StlParser {
             None => return Err(anyhow!("Failed to read from the file!")),
         }
 
+        // lines that are not valid UTF-8 are skipped instead of ending the file
+        #[allow(clippy::lines_filter_map_ok)]
         let mut lines = lines
             .filter_map(|line| line.ok())
             .filter(|line| !line.trim().is_empty());
@@ -229,6 +315,15 @@ impl StlParser {
     }
 
     pub fn write_to_ascii_file(path: PathBuf, triangles: Triangles) -> anyhow::Result<()> {
+        StlParser::write_to_ascii_file_with_header(path, triangles, "")
+    }
+
+    /// Writes an ASCII STL whose first line carries `header` after the solid name.
+    pub fn write_to_ascii_file_with_header(
+        path: PathBuf,
+        triangles: Triangles,
+        header: &str,
+    ) -> anyhow::Result<()> {
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
@@ -241,7 +336,11 @@ impl StlParser {
                 )
             })?;
 
-        writeln!(file, "solid RustStlParser")?;
+        if header.is_empty() {
+            writeln!(file, "solid RustStlParser")?;
+        } else {
+            writeln!(file, "solid RustStlParser {}", header)?;
+        }
         for triangle in triangles {
             let normal = triangle.normal;
             writeln!(
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use super::{
+    mesh::{edge, Edge, IndexedMesh},
+    stl_parser::Triangles,
+    vec3::{Float, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SubdivisionScheme {
+    /// split every facet into four, keeping the shape
+    Midpoint,
+    /// Loop subdivision, converging to a smooth surface
+    #[default]
+    Loop,
+}
+
+/// Applies `iterations` subdivision steps; every step quadruples the facet count.
+pub fn subdivide(triangles: &mut Triangles, scheme: SubdivisionScheme, iterations: usize) {
+    let mut mesh = IndexedMesh::from_triangles(triangles);
+    for _ in 0..iterations {
+        mesh = match scheme {
+            SubdivisionScheme::Midpoint => midpoint_subdivision(&mesh),
+            SubdivisionScheme::Loop => loop_subdivision(&mesh),
+        };
+    }
+    *triangles = mesh.to_triangles();
+}
+
+/// Inserts a vertex in the middle of every edge without moving the existing ones.
+pub fn midpoint_subdivision(mesh: &IndexedMesh) -> IndexedMesh {
+    refine(mesh, mesh.vertices.clone(), |(a, b), _| {
+        (mesh.vertices[a] + mesh.vertices[b]) / 2.0
+    })
+}
+
+/// One step of Loop subdivision.
+///
+/// Interior edges and vertices use the usual Loop masks. Edges with a single
+/// facet follow the boundary rules (midpoints and the 1/8, 3/4, 1/8 vertex
+/// mask), so open borders are smoothed as curves and do not shrink inward.
+/// Vertices on non-manifold edges stay where they are.
+pub fn loop_subdivision(mesh: &IndexedMesh) -> IndexedMesh {
+    let edge_faces = mesh.edge_faces();
+    let mut neighbours = vec![Vec::new(); mesh.vertices.len()];
+    let mut border_neighbours = vec![Vec::new(); mesh.vertices.len()];
+    let mut pinned = vec![false; mesh.vertices.len()];
+    for (&(a, b), faces) in &edge_faces {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+        match faces.len() {
+            1 => {
+                border_neighbours[a].push(b);
+                border_neighbours[b].push(a);
+            }
+            2 => {}
+            _ => {
+                pinned[a] = true;
+                pinned[b] = true;
+            }
+        }
+    }
+
+    let vertices = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(v, &position)| {
+            let sum = |others: &[usize]| {
+                others.iter().fold(Vec3::from((0.0, 0.0, 0.0)), |sum, &w| {
+                    sum + mesh.vertices[w]
+                })
+            };
+            if pinned[v] || neighbours[v].is_empty() {
+                position
+            } else if !border_neighbours[v].is_empty() {
+                if border_neighbours[v].len() == 2 {
+                    position * 0.75 + sum(&border_neighbours[v]) * 0.125
+                } else {
+                    position
+                }
+            } else {
+                let n = neighbours[v].len() as Float;
+                let beta = (0.625 - (0.375 + 0.25 * (2.0 * PI / n).cos()).powi(2)) / n;
+                position * (1.0 - n * beta) + sum(&neighbours[v]) * beta
+            }
+        })
+        .collect();
+
+    refine(mesh, vertices, |(a, b), faces| {
+        let (pa, pb) = (mesh.vertices[a], mesh.vertices[b]);
+        if faces.len() != 2 {
+            return (pa + pb) / 2.0;
+        }
+        let opposite = faces.iter().fold(Vec3::from((0.0, 0.0, 0.0)), |sum, &f| {
+            let face = mesh.faces[f];
+            let c = face.into_iter().find(|&v| v != a && v != b).unwrap();
+            sum + mesh.vertices[c]
+        });
+        (pa + pb) * 0.375 + opposite * 0.125
+    })
+}
+
+/// Splits every face into four, using `vertices` for the original corners and
+/// `edge_point` for the new vertex on each edge.
+fn refine(
+    mesh: &IndexedMesh,
+    mut vertices: Vec<Vec3>,
+    edge_point: impl Fn(Edge, &[usize]) -> Vec3,
+) -> IndexedMesh {
+    let mut edge_vertices: HashMap<Edge, usize> = HashMap::new();
+    for (e, faces) in mesh.edge_faces() {
+        edge_vertices.insert(e, vertices.len());
+        vertices.push(edge_point(e, &faces));
+    }
+
+    let faces = mesh
+        .faces
+        .iter()
+        .filter(|face| !IndexedMesh::is_degenerate(face))
+        .flat_map(|&[a, b, c]| {
+            let ab = edge_vertices[&edge(a, b)];
+            let bc = edge_vertices[&edge(b, c)];
+            let ca = edge_vertices[&edge(c, a)];
+            [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+        })
+        .collect();
+
+    IndexedMesh { vertices, faces }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        fixtures::tetrahedron, mass_properties::MassProperties, mesh_report::MeshReport,
+        stl_generator::StlGenerator, stl_parser::Triangle,
+    };
+
+    #[test]
+    fn test_midpoint_keeps_shape() {
+        let mut triangles = tetrahedron(0.0);
+        subdivide(&mut triangles, SubdivisionScheme::Midpoint, 2);
+        assert_eq!(triangles.len(), 64);
+        assert!(MeshReport::new(&triangles).is_watertight());
+        assert!((MassProperties::new(&triangles, 1.0).volume - 1.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_loop_smooths_closed_mesh() {
+        let mut triangles = tetrahedron(0.0);
+        subdivide(&mut triangles, SubdivisionScheme::Loop, 3);
+        let volume = MassProperties::new(&triangles, 1.0).volume;
+        assert!(volume < 1.0 / 6.0 && volume > 0.0);
+
+        let mut cone = StlGenerator::generate_cone(16, 1.0, 2.0, (0.0, 0.0, 0.0).into());
+        subdivide(&mut cone, SubdivisionScheme::Loop, 1);
+        let report = MeshReport::new(&cone);
+        assert_eq!(report.facets, 4 * (16 + 14));
+        assert!(report.is_watertight() && report.is_manifold());
+    }
+
+    #[test]
+    fn test_loop_boundary_stays_in_plane() {
+        let mut triangles = vec![
+            Triangle::from_vertices([
+                (0.0, 0.0, 0.0).into(),
+                (1.0, 0.0, 0.0).into(),
+                (1.0, 1.0, 0.0).into(),
+            ]),
+            Triangle::from_vertices([
+                (0.0, 0.0, 0.0).into(),
+                (1.0, 1.0, 0.0).into(),
+                (0.0, 1.0, 0.0).into(),
+            ]),
+        ];
+        subdivide(&mut triangles, SubdivisionScheme::Loop, 2);
+        assert_eq!(triangles.len(), 32);
+        for triangle in &triangles {
+            for v in triangle.vertices {
+                assert_eq!(v.z, 0.0);
+                assert!((0.0..=1.0).contains(&v.x) && (0.0..=1.0).contains(&v.y));
+            }
+        }
+    }
+}
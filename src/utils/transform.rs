@@ -0,0 +1,142 @@
+use super::{
+    bounding_box::Aabb,
+    matrix::Mat4,
+    stl_parser::Triangles,
+    vec3::{Float, Vec3},
+    EPS,
+};
+
+/// Applies an affine transformation to every facet.
+///
+/// Normals go through the inverse transpose of the linear part. Mirroring
+/// transforms (negative determinant) also reverse the winding so the facets
+/// keep facing outward.
+pub fn transform(triangles: &mut Triangles, matrix: &Mat4) {
+    let mirrored = matrix.determinant() < 0.0;
+
+    for triangle in triangles.iter_mut() {
+        triangle.vertices = triangle.vertices.map(|v| matrix.transform_point(v));
+
+        let normal = matrix.transform_normal(triangle.normal);
+        let normal = if mirrored { -normal } else { normal };
+        if mirrored {
+            triangle.vertices.swap(1, 2);
+        }
+
+        triangle.normal = if normal.d3() < EPS {
+            triangle.unit_normal()
+        } else {
+            normal.normalize()
+        };
+    }
+}
+
+/// Moves the centre of the bounding box to the origin.
+pub fn center_on_origin(triangles: &mut Triangles) {
+    let aabb = Aabb::from_triangles(triangles);
+    if !aabb.is_empty() {
+        transform(triangles, &Mat4::translation(-aabb.center()));
+    }
+}
+
+/// Moves the mesh along z so that it rests on the z = 0 plane.
+pub fn drop_to_floor(triangles: &mut Triangles) {
+    let aabb = Aabb::from_triangles(triangles);
+    if !aabb.is_empty() {
+        transform(
+            triangles,
+            &Mat4::translation(Vec3::from((0.0, 0.0, -aabb.min.z))),
+        );
+    }
+}
+
+/// Uniformly scales the mesh around its bounding box centre so that it just
+/// fits into a box of the given size. Returns the applied scale factor.
+pub fn scale_to_fit(triangles: &mut Triangles, size: Vec3) -> Float {
+    let aabb = Aabb::from_triangles(triangles);
+    let extent = aabb.size();
+    let factor = [(size.x, extent.x), (size.y, extent.y), (size.z, extent.z)]
+        .into_iter()
+        .filter(|&(_, extent)| extent > EPS)
+        .map(|(size, extent)| size / extent)
+        .fold(Float::INFINITY, Float::min);
+
+    if !factor.is_finite() {
+        return 1.0;
+    }
+
+    let center = aabb.center();
+    let matrix = Mat4::translation(center)
+        * Mat4::scaling(Vec3::from((factor, factor, factor)))
+        * Mat4::translation(-center);
+    transform(triangles, &matrix);
+    factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::stl_parser::Triangle;
+
+    fn triangle() -> Triangles {
+        vec![Triangle::from_vertices([
+            (0.0, 0.0, 1.0).into(),
+            (2.0, 0.0, 1.0).into(),
+            (0.0, 1.0, 1.0).into(),
+        ])]
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).d3() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_rotation_transforms_normals() {
+        let mut triangles = triangle();
+        transform(
+            &mut triangles,
+            &Mat4::rotation_degrees((90.0, 0.0, 0.0).into()),
+        );
+        assert_close(triangles[0].vertices[1], (2.0, -1.0, 0.0).into());
+        assert_close(triangles[0].normal, (0.0, -1.0, 0.0).into());
+        assert_close(triangles[0].normal, triangles[0].unit_normal());
+    }
+
+    #[test]
+    fn test_non_uniform_scale_keeps_normals_perpendicular() {
+        let mut triangles = vec![Triangle::from_vertices([
+            (1.0, 0.0, 0.0).into(),
+            (0.0, 1.0, 0.0).into(),
+            (0.0, 0.0, 1.0).into(),
+        ])];
+        transform(&mut triangles, &Mat4::scaling((1.0, 2.0, 4.0).into()));
+        assert_close(triangles[0].normal, triangles[0].unit_normal());
+    }
+
+    #[test]
+    fn test_mirror_flips_winding() {
+        let mut triangles = triangle();
+        transform(&mut triangles, &Mat4::mirror((0.0, 0.0, 1.0).into()));
+        assert_close(triangles[0].normal, (0.0, 0.0, -1.0).into());
+        assert_close(triangles[0].normal, triangles[0].unit_normal());
+        assert_eq!(triangles[0].vertices[0].z, -1.0);
+    }
+
+    #[test]
+    fn test_helpers() {
+        let mut triangles = triangle();
+        center_on_origin(&mut triangles);
+        let aabb = Aabb::from_triangles(&triangles);
+        assert_close(aabb.center(), (0.0, 0.0, 0.0).into());
+
+        drop_to_floor(&mut triangles);
+        assert_eq!(Aabb::from_triangles(&triangles).min.z, 0.0);
+
+        let factor = scale_to_fit(&mut triangles, (1.0, 1.0, 1.0).into());
+        assert_eq!(factor, 0.5);
+        assert_close(
+            Aabb::from_triangles(&triangles).size(),
+            (1.0, 0.5, 0.0).into(),
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use super::{
+    bounding_box::Aabb,
+    matrix::Mat4,
+    stl_parser::Triangles,
+    transform::transform,
+    vec3::{Float, Vec3},
+};
+
+/// Length units STL files are commonly authored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Unit {
+    #[value(name = "mm")]
+    Millimeter,
+    #[value(name = "cm")]
+    Centimeter,
+    #[value(name = "m")]
+    Meter,
+    #[value(name = "in")]
+    Inch,
+    #[value(name = "ft")]
+    Foot,
+}
+
+impl Unit {
+    /// Candidates in the order the guess prefers them.
+    const ALL: [Unit; 5] = [
+        Unit::Millimeter,
+        Unit::Inch,
+        Unit::Centimeter,
+        Unit::Meter,
+        Unit::Foot,
+    ];
+
+    pub fn in_millimeters(self) -> Float {
+        match self {
+            Unit::Millimeter => 1.0,
+            Unit::Centimeter => 10.0,
+            Unit::Meter => 1000.0,
+            Unit::Inch => 25.4,
+            Unit::Foot => 304.8,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Millimeter => "mm",
+            Unit::Centimeter => "cm",
+            Unit::Meter => "m",
+            Unit::Inch => "in",
+            Unit::Foot => "ft",
+        }
+    }
+
+    /// Binary STL header recording the unit.
+    pub fn header(self) -> String {
+        format!("UNITS={}", self.symbol())
+    }
+
+    /// Reads back a unit written by [`Unit::header`].
+    pub fn from_header(header: &str) -> Option<Unit> {
+        let symbol = header
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix("UNITS="))?;
+        Unit::ALL.into_iter().find(|unit| unit.symbol() == symbol)
+    }
+}
+
+/// Scales the mesh from one unit to another.
+pub fn convert_units(triangles: &mut Triangles, from: Unit, to: Unit) {
+    if from == to {
+        return;
+    }
+    let factor = from.in_millimeters() / to.in_millimeters();
+    transform(
+        triangles,
+        &Mat4::scaling(Vec3::from((factor, factor, factor))),
+    );
+}
+
+/// Guesses the unit from the size of the bounding box.
+///
+/// Picks the first unit (millimeters, then inches, centimeters, meters and
+/// feet) for which the largest dimension of the part falls between 10 mm and
+/// 500 mm, or else the unit that comes closest to that range.
+pub fn guess_unit(triangles: &Triangles) -> Unit {
+    const PLAUSIBLE_MM: (Float, Float) = (10.0, 500.0);
+
+    let size = Aabb::from_triangles(triangles).size();
+    let largest = size.x.max(size.y).max(size.z);
+    if largest <= 0.0 {
+        return Unit::Millimeter;
+    }
+
+    let distance = |unit: Unit| {
+        let mm = (largest * unit.in_millimeters()).log10();
+        let (lo, hi) = (PLAUSIBLE_MM.0.log10(), PLAUSIBLE_MM.1.log10());
+        (lo - mm).max(mm - hi).max(0.0)
+    };
+
+    Unit::ALL
+        .into_iter()
+        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::stl_parser::Triangle;
+
+    fn part(size: Float) -> Triangles {
+        vec![Triangle::from_vertices([
+            (0.0, 0.0, 0.0).into(),
+            (size, 0.0, 0.0).into(),
+            (0.0, size / 2.0, 0.0).into(),
+        ])]
+    }
+
+    #[test]
+    fn test_guess_unit() {
+        assert_eq!(guess_unit(&part(120.0)), Unit::Millimeter);
+        assert_eq!(guess_unit(&part(4.0)), Unit::Inch);
+        assert_eq!(guess_unit(&part(0.15)), Unit::Meter);
+        assert_eq!(guess_unit(&part(20000.0)), Unit::Millimeter);
+    }
+
+    #[test]
+    fn test_convert_units() {
+        let mut triangles = part(2.0);
+        convert_units(&mut triangles, Unit::Inch, Unit::Millimeter);
+        assert!((triangles[0].vertices[1].x - 50.8).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        for unit in Unit::ALL {
+            assert_eq!(Unit::from_header(&unit.header()), Some(unit));
+        }
+        assert_eq!(Unit::from_header("solid part"), None);
+    }
+}
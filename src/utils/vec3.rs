@@ -145,6 +145,12 @@ impl Vec3 {
         Vec3::from((self.y, self.z, self.x)) * Vec3::from((rhs.z, rhs.x, rhs.y))
             - Vec3::from((self.z, self.x, self.y)) * Vec3::from((rhs.y, rhs.z, rhs.x))
     }
+    pub fn min(self, rhs: Vec3) -> Self {
+        Vec3::from((self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z)))
+    }
+    pub fn max(self, rhs: Vec3) -> Self {
+        Vec3::from((self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z)))
+    }
 
     pub fn write_le_bytes<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
         writer.write_all(&self.x.to_le_bytes())?;
@@ -0,0 +1,446 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context};
+
+use super::{
+    bounding_box::Aabb,
+    bvh::{Bvh, Ray},
+    containment::contains,
+    point::{cross, dot, sub, to_f64, Point},
+    stl_parser::{StlParser, Triangle, Triangles},
+    vec3::{Float, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum VoxelMode {
+    /// voxels touched by a facet
+    Surface,
+    /// surface voxels and voxels whose center lies inside the mesh
+    #[default]
+    Solid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VoxelFormat {
+    /// blocky mesh of the voxel faces
+    Stl,
+    /// one byte per voxel, x fastest, then y, then z, with the grid described
+    /// in a JSON file next to it
+    Raw,
+    /// MagicaVoxel model
+    Vox,
+}
+
+impl VoxelFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            VoxelFormat::Stl => "stl",
+            VoxelFormat::Raw => "raw",
+            VoxelFormat::Vox => "vox",
+        }
+    }
+}
+
+/// Dense grid of cubic voxels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+    /// Minimum corner of voxel `[0, 0, 0]`.
+    pub origin: Vec3,
+    /// Edge length of a voxel.
+    pub size: Float,
+    pub dims: [usize; 3],
+    cells: Vec<bool>,
+}
+
+impl VoxelGrid {
+    pub fn new(origin: Vec3, size: Float, dims: [usize; 3]) -> Self {
+        VoxelGrid {
+            origin,
+            size,
+            dims,
+            cells: vec![false; dims[0] * dims[1] * dims[2]],
+        }
+    }
+
+    /// Grid with the listed voxels set.
+    pub fn from_sparse(origin: Vec3, size: Float, dims: [usize; 3], voxels: &[[usize; 3]]) -> Self {
+        let mut grid = VoxelGrid::new(origin, size, dims);
+        for &voxel in voxels {
+            grid.set(voxel, true);
+        }
+        grid
+    }
+
+    fn index(&self, [i, j, k]: [usize; 3]) -> usize {
+        i + self.dims[0] * (j + self.dims[1] * k)
+    }
+
+    /// Voxels outside the grid are empty.
+    pub fn get(&self, voxel: [usize; 3]) -> bool {
+        (0..3).all(|a| voxel[a] < self.dims[a]) && self.cells[self.index(voxel)]
+    }
+
+    pub fn set(&mut self, voxel: [usize; 3], value: bool) {
+        let index = self.index(voxel);
+        self.cells[index] = value;
+    }
+
+    /// Number of set voxels.
+    pub fn count(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// Set voxels in storage order.
+    pub fn to_sparse(&self) -> Vec<[usize; 3]> {
+        let [nx, ny, _] = self.dims;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &cell)| cell)
+            .map(|(n, _)| [n % nx, n / nx % ny, n / (nx * ny)])
+            .collect()
+    }
+
+    pub fn center(&self, voxel: [usize; 3]) -> Vec3 {
+        self.origin
+            + Vec3::from((
+                voxel[0] as Float + 0.5,
+                voxel[1] as Float + 0.5,
+                voxel[2] as Float + 0.5,
+            )) * self.size
+    }
+
+    /// Closed mesh of the voxel faces that do not touch another voxel, with
+    /// outward normals.
+    pub fn to_triangles(&self) -> Triangles {
+        let mut triangles = Triangles::new();
+        for voxel in self.to_sparse() {
+            let corner = |d: [usize; 3]| {
+                self.origin
+                    + Vec3::from((
+                        (voxel[0] + d[0]) as Float,
+                        (voxel[1] + d[1]) as Float,
+                        (voxel[2] + d[2]) as Float,
+                    )) * self.size
+            };
+            for axis in 0..3 {
+                for side in 0..2 {
+                    let mut neighbour = voxel;
+                    if side == 0 {
+                        if voxel[axis] > 0 {
+                            neighbour[axis] -= 1;
+                            if self.get(neighbour) {
+                                continue;
+                            }
+                        }
+                    } else {
+                        neighbour[axis] += 1;
+                        if self.get(neighbour) {
+                            continue;
+                        }
+                    }
+
+                    // corners of the face counter-clockwise seen from outside
+                    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let offset = |da: usize, db: usize| {
+                        let mut d = [0; 3];
+                        d[axis] = side;
+                        d[a] = da;
+                        d[b] = db;
+                        corner(d)
+                    };
+                    let mut quad = [offset(0, 0), offset(1, 0), offset(1, 1), offset(0, 1)];
+                    if side == 0 {
+                        quad.reverse();
+                    }
+                    triangles.push(Triangle::from_vertices([quad[0], quad[1], quad[2]]));
+                    triangles.push(Triangle::from_vertices([quad[0], quad[2], quad[3]]));
+                }
+            }
+        }
+        triangles
+    }
+
+    /// One byte per voxel, 1 for set voxels, with x running fastest.
+    pub fn write_raw<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let bytes: Vec<u8> = self.cells.iter().map(|&cell| cell as u8).collect();
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Dimensions, origin and voxel size of a raw export as JSON, e.g.
+    /// `{"dims":[2,3,4],"origin":[0,0,0],"size":1}`. The origin is the minimum
+    /// corner of the first voxel.
+    pub fn write_raw_info<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let [x, y, z] = self.dims;
+        let o = self.origin;
+        writeln!(
+            writer,
+            r#"{{"dims":[{},{},{}],"origin":[{},{},{}],"size":{}}}"#,
+            x, y, z, o.x, o.y, o.z, self.size
+        )?;
+        Ok(())
+    }
+
+    /// MagicaVoxel `.vox` (version 150) with a `SIZE` and an `XYZI` chunk. The
+    /// format stores coordinates as bytes, so every axis is limited to 256
+    /// voxels.
+    pub fn write_vox<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        if self.dims.iter().any(|&n| n > 256) {
+            return Err(anyhow!(
+                "MagicaVoxel models are limited to 256 voxels per axis!"
+            ));
+        }
+        let voxels = self.to_sparse();
+        let chunk = |writer: &mut W, id: &[u8; 4], content: u32, children: u32| {
+            writer.write_all(id)?;
+            writer.write_all(&content.to_le_bytes())?;
+            writer.write_all(&children.to_le_bytes())
+        };
+
+        writer.write_all(b"VOX ")?;
+        writer.write_all(&150u32.to_le_bytes())?;
+        let xyzi = 4 + 4 * voxels.len() as u32;
+        chunk(writer, b"MAIN", 0, 12 + 12 + 12 + xyzi)?;
+        chunk(writer, b"SIZE", 12, 0)?;
+        for n in self.dims {
+            writer.write_all(&(n as u32).to_le_bytes())?;
+        }
+        chunk(writer, b"XYZI", xyzi, 0)?;
+        writer.write_all(&(voxels.len() as u32).to_le_bytes())?;
+        for [i, j, k] in voxels {
+            // colour 1 of the default palette
+            writer.write_all(&[i as u8, j as u8, k as u8, 1])?;
+        }
+        Ok(())
+    }
+}
+
+/// Voxelizes the mesh into cubes of edge `size` covering its bounding box.
+///
+/// Surface voxels are found with the separating axis test of every facet
+/// against the voxels its box overlaps. Solid mode adds the voxels whose
+/// centers lie inside, by the parity of crossings of a ray up every column of
+/// voxels; columns whose ray touches an edge fall back to a per voxel
+/// containment test.
+pub fn voxelize(triangles: &Triangles, size: Float, mode: VoxelMode) -> VoxelGrid {
+    let bounds = Aabb::from_triangles(triangles);
+    if bounds.is_empty() {
+        return VoxelGrid::new(Vec3::from((0.0, 0.0, 0.0)), size, [0; 3]);
+    }
+    let extent = to_f64(bounds.size());
+    let dims = extent.map(|e| ((e / size as f64).ceil() as usize).max(1));
+    let mut grid = VoxelGrid::new(bounds.min, size, dims);
+
+    let origin = to_f64(bounds.min);
+    let h = size as f64;
+    // range of voxels touching [low, high] along an axis, a coordinate on a
+    // voxel border touches the voxels on both sides
+    let cells = |low: f64, high: f64, axis: usize| {
+        let first = ((low - origin[axis]) / h).ceil() - 1.0;
+        let last = ((high - origin[axis]) / h).floor();
+        let clamp = |t: f64| (t.max(0.0) as usize).min(dims[axis] - 1);
+        clamp(first)..=clamp(last)
+    };
+    for triangle in triangles {
+        let vertices = triangle.vertices.map(to_f64);
+        let aabb = Aabb::from_triangle(triangle);
+        let (low, high) = (to_f64(aabb.min), to_f64(aabb.max));
+        for k in cells(low[2], high[2], 2) {
+            for j in cells(low[1], high[1], 1) {
+                for i in cells(low[0], high[0], 0) {
+                    let center = to_f64(grid.center([i, j, k]));
+                    if triangle_box_overlap(center, h / 2.0, &vertices) {
+                        grid.set([i, j, k], true);
+                    }
+                }
+            }
+        }
+    }
+
+    if mode == VoxelMode::Solid {
+        let bvh = Bvh::new(triangles);
+        let below = bounds.min.z - size;
+        for j in 0..dims[1] {
+            for i in 0..dims[0] {
+                let bottom = grid.center([i, j, 0]);
+                let ray = Ray::new(
+                    Vec3::from((bottom.x, bottom.y, below)),
+                    Vec3::from((0.0, 0.0, 1.0)),
+                );
+                let hits = bvh.all_hits(&ray);
+                let ambiguous = hits
+                    .iter()
+                    .any(|hit| hit.u.min(hit.v).min(1.0 - hit.u - hit.v) < 1e-6);
+                for k in 0..dims[2] {
+                    let center = grid.center([i, j, k]);
+                    let inside = if ambiguous {
+                        contains(&bvh, center)
+                    } else {
+                        let height = center.z - below;
+                        hits.iter().filter(|hit| hit.distance < height).count() % 2 == 1
+                    };
+                    if inside {
+                        grid.set([i, j, k], true);
+                    }
+                }
+            }
+        }
+    }
+    grid
+}
+
+/// Separating axis test of a triangle against the cube around `center`
+/// (Akenine-Möller, Fast 3D Triangle-Box Overlap Testing). Touching counts as
+/// overlap, so facets on voxel borders mark the voxels on both sides.
+fn triangle_box_overlap(center: Point, half: f64, triangle: &[Point; 3]) -> bool {
+    let v = triangle.map(|p| sub(p, center));
+    let separated = |axis: Point| {
+        let projections = v.map(|p| dot(p, axis));
+        let radius = half * (axis[0].abs() + axis[1].abs() + axis[2].abs());
+        let min = projections.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = projections
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        min > radius || max < -radius
+    };
+
+    let edges = [sub(v[1], v[0]), sub(v[2], v[1]), sub(v[0], v[2])];
+    let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    if axes.iter().any(|&axis| separated(axis)) {
+        return false;
+    }
+    if separated(cross(edges[0], edges[1])) {
+        return false;
+    }
+    !edges
+        .iter()
+        .any(|&edge| axes.iter().any(|&axis| separated(cross(axis, edge))))
+}
+
+/// Writes the grid in `format`; STL goes out binary.
+///
+/// Raw exports get a `.json` file of the same name with the grid layout, see
+/// [`VoxelGrid::write_raw_info`].
+pub fn write_voxels(path: PathBuf, grid: &VoxelGrid, format: VoxelFormat) -> anyhow::Result<()> {
+    if format == VoxelFormat::Stl {
+        return StlParser::write_to_binary_file(path, grid.to_triangles());
+    }
+    let create = |path: &PathBuf| {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| {
+                format!(
+                    "Failed to create a file for writing!\nPath: \'{}\'",
+                    path.display()
+                )
+            })?;
+        anyhow::Ok(BufWriter::new(file))
+    };
+    let mut writer = create(&path)?;
+    match format {
+        VoxelFormat::Raw => {
+            grid.write_raw(&mut writer)?;
+            let mut info = create(&path.with_extension("json"))?;
+            grid.write_raw_info(&mut info)?;
+            info.flush()?;
+        }
+        _ => grid.write_vox(&mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        fixtures::cuboid, mass_properties::MassProperties, mesh_report::MeshReport,
+    };
+
+    #[test]
+    fn test_cube() {
+        let triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let surface = voxelize(&triangles, 0.25, VoxelMode::Surface);
+        assert_eq!(surface.dims, [4, 4, 4]);
+        assert_eq!(surface.count(), 64 - 8);
+        assert!(!surface.get([1, 2, 2]));
+
+        let solid = voxelize(&triangles, 0.25, VoxelMode::Solid);
+        assert_eq!(solid.count(), 64);
+        let sparse = solid.to_sparse();
+        assert_eq!(sparse[5], [1, 1, 0]);
+        assert_eq!(
+            VoxelGrid::from_sparse(solid.origin, solid.size, solid.dims, &sparse),
+            solid
+        );
+    }
+
+    #[test]
+    fn test_cavity() {
+        let mut triangles = cuboid((0.0, 0.0, 0.0).into(), (1.0, 1.0, 1.0).into());
+        let mut cavity = cuboid((0.25, 0.25, 0.25).into(), (0.75, 0.75, 0.75).into());
+        cavity.iter_mut().for_each(|t| t.flip());
+        triangles.extend(cavity);
+
+        let grid = voxelize(&triangles, 0.125, VoxelMode::Solid);
+        assert_eq!(grid.dims, [8, 8, 8]);
+        // only the voxels that do not touch the cavity walls stay empty
+        assert_eq!(grid.count(), 512 - 8);
+        assert!(!grid.get([3, 4, 3]));
+    }
+
+    #[test]
+    fn test_blocky_mesh() {
+        let mut grid = VoxelGrid::new((1.0, 2.0, 3.0).into(), 0.5, [4, 2, 2]);
+        grid.set([0, 0, 0], true);
+        grid.set([1, 0, 0], true);
+        grid.set([3, 1, 1], true);
+        let triangles = grid.to_triangles();
+        assert_eq!(triangles.len(), 10 * 2 + 6 * 2);
+
+        let report = MeshReport::new(&triangles);
+        assert!(report.is_watertight() && report.is_manifold());
+        assert_eq!(report.components, 2);
+        assert!((MassProperties::new(&triangles, 1.0).volume - 3.0 * 0.125).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut grid = VoxelGrid::new((0.0, 0.0, 0.0).into(), 1.0, [2, 3, 4]);
+        grid.set([1, 2, 3], true);
+
+        let mut raw = Vec::new();
+        grid.write_raw(&mut raw).unwrap();
+        assert_eq!(raw.len(), 24);
+        assert_eq!(raw.iter().position(|&b| b == 1), Some(23));
+        let mut info = Vec::new();
+        VoxelGrid::new((-1.5, 0.0, 2.0).into(), 0.5, [2, 3, 4])
+            .write_raw_info(&mut info)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(info).unwrap(),
+            "{\"dims\":[2,3,4],\"origin\":[-1.5,0,2],\"size\":0.5}\n"
+        );
+
+        let mut vox = Vec::new();
+        grid.write_vox(&mut vox).unwrap();
+        assert_eq!(&vox[..8], b"VOX \x96\0\0\0");
+        assert_eq!(&vox[8..12], b"MAIN");
+        assert_eq!(&vox[20..24], b"SIZE");
+        assert_eq!(&vox[32..44], [2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0]);
+        assert_eq!(&vox[44..48], b"XYZI");
+        assert_eq!(&vox[vox.len() - 8..], [1, 0, 0, 0, 1, 2, 3, 1]);
+
+        let large = VoxelGrid::new((0.0, 0.0, 0.0).into(), 1.0, [257, 1, 1]);
+        assert!(large.write_vox(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use stl_parser::utils::{
+    bounding_box::Aabb, mass_properties::MassProperties, mesh_report::MeshReport,
+    stl_generator::StlGenerator, stl_parser::StlParser, units::Unit, StlFormat,
+};
+
+/// Fresh path in the temporary directory, unique per test.
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("stl_parser_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn run(args: &[&str]) -> std::process::Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_stl_parser"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn test_repair_flipped_facet_next_to_hole() {
+    // unit cube with one facet missing and its neighbour on the same side flipped
+    let mut triangles = StlGenerator::generate_cube(1.0, (0.0, 0.0, 0.0).into());
+    triangles.remove(1);
+    triangles[0].flip();
+    let input = temp_path("repair_in.stl");
+    let output = temp_path("repair_out.stl");
+    StlParser::write_stl(input.clone(), triangles, StlFormat::Binary).unwrap();
+
+    run(&[
+        "-o",
+        output.to_str().unwrap(),
+        "repair",
+        input.to_str().unwrap(),
+        "--orient",
+        "--fill-holes",
+    ]);
+
+    let repaired = StlParser::read_stl(output.clone()).unwrap();
+    assert_eq!(repaired.len(), 12);
+    let report = MeshReport::new(&repaired);
+    assert!(report.is_watertight() && report.is_manifold());
+    let volume = MassProperties::new(&repaired, 1.0).volume;
+    assert!((volume - 1.0).abs() < 1e-6, "volume {}", volume);
+
+    let _ = std::fs::remove_file(input);
+    let _ = std::fs::remove_file(output);
+}
+
+/// Unit recorded in the header of the file.
+fn header_unit(path: &Path) -> Option<Unit> {
+    Unit::from_header(&StlParser::read_binary_header(path.to_path_buf()).unwrap())
+}
+
+#[test]
+fn test_unit_is_kept_in_the_header() {
+    let cube = temp_path("units_cube.stl");
+    let tagged = temp_path("units_tagged.stl");
+    let moved = temp_path("units_moved.stl");
+    let ascii = temp_path("units_ascii.stl");
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+
+    run(&["-o", &path(&cube), "cube", "2", "0 0 0"]);
+    assert_eq!(header_unit(&cube), None);
+    run(&["-o", &path(&tagged), "parse", "--units", "in", &path(&cube)]);
+    assert_eq!(header_unit(&tagged), Some(Unit::Inch));
+
+    // later rewrites carry the unit along, in either format
+    run(&[
+        "-o",
+        &path(&moved),
+        "transform",
+        &path(&tagged),
+        "--translate",
+        "1 0 0",
+    ]);
+    assert_eq!(header_unit(&moved), Some(Unit::Inch));
+    run(&[
+        "-o",
+        &path(&ascii),
+        "--output-format",
+        "ascii",
+        "repair",
+        &path(&moved),
+    ]);
+    assert_eq!(header_unit(&ascii), Some(Unit::Inch));
+    assert_eq!(StlParser::read_stl(ascii.clone()).unwrap().len(), 12);
+
+    for path in [cube, tagged, moved, ascii] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_diff_exit_status() {
+    let a = temp_path("diff_a.stl");
+    let b = temp_path("diff_b.stl");
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+    run(&["-o", &path(&a), "cube", "1", "0 0 0"]);
+    run(&["-o", &path(&b), "cube", "1", "0 0 0.5"]);
+
+    run(&["diff", &path(&a), &path(&a)]);
+    let diff = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_stl_parser"))
+            .arg("diff")
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+    };
+    assert_eq!(diff(&[&path(&a), &path(&b)]).code(), Some(1));
+    let missing = temp_path("diff_missing.stl");
+    assert_eq!(diff(&[&path(&a), &path(&missing)]).code(), Some(2));
+    assert_eq!(
+        diff(&["--tolerance=-1", &path(&a), &path(&a)]).code(),
+        Some(2)
+    );
+
+    for path in [a, b] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_merge_with_negative_offset() {
+    let cube = temp_path("merge_cube.stl");
+    let merged = temp_path("merge_out.stl");
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+    run(&["-o", &path(&cube), "cube", "1", "0 0 0"]);
+
+    run(&[
+        "-o",
+        &path(&merged),
+        "merge",
+        &path(&cube),
+        &path(&cube),
+        "--translate",
+        "-2 0 0",
+        "--translate",
+        "0 0 0",
+    ]);
+    let triangles = StlParser::read_stl(merged.clone()).unwrap();
+    assert_eq!(triangles.len(), 24);
+    let bounds = Aabb::from_triangles(&triangles);
+    assert_eq!(bounds.min.x, -2.5);
+    assert_eq!(bounds.max.x, 0.5);
+
+    for path in [cube, merged] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_subdivide_rejects_too_many_iterations() {
+    let cube = temp_path("subdivide_cube.stl");
+    let path = cube.to_str().unwrap();
+    run(&["-o", path, "cube", "1", "0 0 0"]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_stl_parser"))
+        .args(["subdivide", path, "--iterations", "20"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("fewer iterations"));
+
+    let _ = std::fs::remove_file(cube);
+}
+
+#[test]
+fn test_compare_against_empty_mesh() {
+    let cube = temp_path("compare_cube.stl");
+    let empty = temp_path("compare_empty.stl");
+    let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+    run(&["-o", &path(&cube), "cube", "1", "0 0 0"]);
+    StlParser::write_stl(empty.clone(), Vec::new(), StlFormat::Binary).unwrap();
+
+    run(&[
+        "compare",
+        &path(&cube),
+        &path(&cube),
+        "--tolerance",
+        "0.001",
+    ]);
+    for (a, b) in [(&cube, &empty), (&empty, &cube)] {
+        let output = Command::new(env!("CARGO_BIN_EXE_stl_parser"))
+            .args(["compare", &path(a), &path(b), "--tolerance", "0.001"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("hausdorff: inf"));
+    }
+
+    for path in [cube, empty] {
+        let _ = std::fs::remove_file(path);
+    }
+}